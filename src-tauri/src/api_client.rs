@@ -2,6 +2,13 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::sync::{Mutex, OnceLock};
+
+/// Whether the last request reached the server; `None` before the first one
+static ONLINE: Mutex<Option<bool>> = Mutex::new(None);
+
+/// Called with the new state whenever the server becomes reachable or not
+static CONNECTIVITY_LISTENER: OnceLock<Box<dyn Fn(bool) + Send + Sync>> = OnceLock::new();
 
 #[derive(Debug, Serialize)]
 pub struct ClassifyRequest {
@@ -32,6 +39,33 @@ pub struct ActionLogRequest {
     pub confidence: f64,
 }
 
+/// Register the function told about connectivity changes; only the first call counts
+pub fn on_connectivity_change(listener: impl Fn(bool) + Send + Sync + 'static) {
+    let _ = CONNECTIVITY_LISTENER.set(Box::new(listener));
+}
+
+pub fn is_online() -> Option<bool> {
+    ONLINE.lock().map(|online| *online).unwrap_or(None)
+}
+
+/// Note whether a request reached the server, turning transport errors into messages
+fn network<T>(result: reqwest::Result<T>) -> Result<T, String> {
+    let online = result.is_ok();
+    let previous = ONLINE
+        .lock()
+        .map(|mut state| state.replace(online))
+        .unwrap_or(None);
+
+    if previous != Some(online) {
+        log::info!("API server is {}", if online { "reachable" } else { "unreachable" });
+        if let Some(listener) = CONNECTIVITY_LISTENER.get() {
+            listener(online);
+        }
+    }
+
+    result.map_err(|e| format!("Network error: {}", e))
+}
+
 /// Login to the API and get tokens
 pub async fn login(api_url: &str, email: &str, password: &str) -> Result<Value, String> {
     let client = reqwest::Client::new();
@@ -43,8 +77,8 @@ pub async fn login(api_url: &str, email: &str, password: &str) -> Result<Value,
             "password": password,
         }))
         .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .await;
+    let response = network(response)?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -58,29 +92,6 @@ pub async fn login(api_url: &str, email: &str, password: &str) -> Result<Value,
         .map_err(|e| format!("Parse error: {}", e))
 }
 
-/// Refresh access token
-pub async fn refresh_token(api_url: &str, refresh_token: &str) -> Result<Value, String> {
-    let client = reqwest::Client::new();
-    
-    let response = client
-        .post(format!("{}/api/auth/refresh", api_url))
-        .json(&serde_json::json!({
-            "refresh_token": refresh_token,
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err("Token refresh failed".to_string());
-    }
-
-    response
-        .json()
-        .await
-        .map_err(|e| format!("Parse error: {}", e))
-}
-
 /// Classify a file using the API
 pub async fn classify_file(
     api_url: &str,
@@ -94,8 +105,8 @@ pub async fn classify_file(
         .header("Authorization", format!("Bearer {}", token))
         .json(request)
         .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .await;
+    let response = network(response)?;
 
     if response.status().as_u16() == 402 {
         return Err("Plan limit reached. Upgrade to Pro for unlimited sorting.".to_string());
@@ -125,8 +136,8 @@ pub async fn log_action(
         .header("Authorization", format!("Bearer {}", token))
         .json(request)
         .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .await;
+    let response = network(response)?;
 
     if !response.status().is_success() {
         let status = response.status();
@@ -139,16 +150,16 @@ pub async fn log_action(
         .map_err(|e| format!("Parse error: {}", e))
 }
 
-/// Get recent actions for display
-pub async fn get_recent_actions(api_url: &str, token: &str) -> Result<Value, String> {
+/// One page of the server-side action history
+pub async fn get_history(api_url: &str, token: &str, page: u32, per_page: u32) -> Result<Value, String> {
     let client = reqwest::Client::new();
     
     let response = client
-        .get(format!("{}/api/history?page=1&per_page=5", api_url))
+        .get(format!("{}/api/history?page={}&per_page={}", api_url, page, per_page))
         .header("Authorization", format!("Bearer {}", token))
         .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .await;
+    let response = network(response)?;
 
     if !response.status().is_success() {
         return Ok(serde_json::json!({"actions": []}));
@@ -168,8 +179,8 @@ pub async fn get_rules(api_url: &str, token: &str) -> Result<Value, String> {
         .get(format!("{}/api/rules", api_url))
         .header("Authorization", format!("Bearer {}", token))
         .send()
-        .await
-        .map_err(|e| format!("Network error: {}", e))?;
+        .await;
+    let response = network(response)?;
 
     if !response.status().is_success() {
         return Err("Failed to fetch rules".to_string());
@@ -180,3 +191,67 @@ pub async fn get_rules(api_url: &str, token: &str) -> Result<Value, String> {
         .await
         .map_err(|e| format!("Parse error: {}", e))
 }
+
+/// POST a JSON body to an authenticated endpoint and return the JSON reply
+async fn post_json(api_url: &str, token: &str, path: &str, body: &Value) -> Result<Value, String> {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}{}", api_url, path))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(body)
+        .send()
+        .await;
+    let response = network(response)?;
+
+    if response.status().as_u16() == 401 {
+        return Err("Session expired, please log in again".to_string());
+    }
+
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!("API error: {}", status));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Get the user's usage statistics
+pub async fn get_user_stats(api_url: &str, token: &str) -> Result<Value, String> {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/api/user/stats", api_url))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+    let response = network(response)?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(format!("Failed to fetch stats: {}", status));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Extract a text preview from file content for auto-organize
+pub async fn extract_content(api_url: &str, token: &str, request: &Value) -> Result<Value, String> {
+    post_json(api_url, token, "/api/auto-organize/extract-content", request).await
+}
+
+/// Ask the server to group files into folders
+pub async fn analyze_organize(api_url: &str, token: &str, request: &Value) -> Result<Value, String> {
+    post_json(api_url, token, "/api/auto-organize/analyze", request).await
+}
+
+/// Suggest sorting rules from an auto-organize result
+pub async fn generate_rules(api_url: &str, token: &str, request: &Value) -> Result<Value, String> {
+    post_json(api_url, token, "/api/auto-organize/generate-rules", request).await
+}
@@ -0,0 +1,195 @@
+//! Launching the agent when the user logs in.
+//!
+//! Each platform implements `Autostart`; `platform()` returns the one for the
+//! current OS, or `None` where start on boot is not supported yet.
+
+/// Flag added to the autostart command so the window starts hidden
+pub const MINIMIZED_FLAG: &str = "--minimized";
+
+/// Flag carrying a non-default config directory into the autostart command
+pub const CONFIG_DIR_FLAG: &str = "--config-dir";
+
+pub trait Autostart {
+    /// Whether the agent is currently registered to start on login
+    fn is_enabled(&self) -> Result<bool, String>;
+
+    /// Register the agent, replacing any stale registration
+    fn enable(&self) -> Result<(), String>;
+
+    /// Remove the registration, if any
+    fn disable(&self) -> Result<(), String>;
+}
+
+/// Autostart backend for this platform
+pub fn platform() -> Option<Box<dyn Autostart>> {
+    #[cfg(target_os = "linux")]
+    {
+        xdg::XdgAutostart::new().map(|a| Box::new(a) as Box<dyn Autostart>)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Register or unregister the agent to match `enabled`
+pub fn apply(enabled: bool) -> Result<(), String> {
+    match platform() {
+        Some(autostart) if enabled => autostart.enable(),
+        Some(autostart) => autostart.disable(),
+        None if enabled => Err("Start on boot is not supported on this platform".to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Bring the registration in line with the config at startup
+pub fn reconcile(enabled: bool) {
+    let Some(autostart) = platform() else {
+        if enabled {
+            log::warn!("start_on_boot is set but not supported on this platform");
+        }
+        return;
+    };
+
+    match autostart.is_enabled() {
+        Ok(registered) if registered != enabled => log::warn!(
+            "Autostart registration ({}) does not match start_on_boot ({}), fixing",
+            registered,
+            enabled
+        ),
+        Ok(_) => {}
+        Err(e) => log::warn!("Cannot read autostart registration: {}", e),
+    }
+
+    // Enabling again also refreshes an Exec line left behind by a moved binary
+    if let Err(e) = apply(enabled) {
+        log::error!("Failed to update autostart registration: {}", e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod xdg {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{Autostart, CONFIG_DIR_FLAG, MINIMIZED_FLAG};
+    use crate::config_overrides;
+    use crate::fs_util;
+
+    /// `~/.config/autostart/filesorter.desktop`, per the XDG autostart spec
+    pub struct XdgAutostart {
+        path: PathBuf,
+    }
+
+    impl XdgAutostart {
+        pub fn new() -> Option<Self> {
+            let path = dirs::config_dir()?
+                .join("autostart")
+                .join("filesorter.desktop");
+            Some(Self { path })
+        }
+
+        fn desktop_entry() -> Result<String, String> {
+            // AppImages run from a temporary mount; start the image itself
+            let exe = match std::env::var_os("APPIMAGE") {
+                Some(appimage) => PathBuf::from(appimage),
+                None => std::env::current_exe().map_err(|e| e.to_string())?,
+            };
+
+            let mut exec = vec![quote_exec_arg(&exe.to_string_lossy()), MINIMIZED_FLAG.to_string()];
+
+            // A relocated config must survive the login session, whether it
+            // came from --config-dir or FILESORTER_CONFIG_DIR
+            if let Some(dir) = config_overrides::config_dir() {
+                let dir = dir.canonicalize().unwrap_or(dir);
+                exec.push(CONFIG_DIR_FLAG.to_string());
+                exec.push(quote_exec_arg(&dir.to_string_lossy()));
+            }
+
+            Ok(format!(
+                "[Desktop Entry]\n\
+                 Type=Application\n\
+                 Name=FileSorter\n\
+                 Comment=Sorts new files into folders\n\
+                 Exec={}\n\
+                 Icon=filesorter\n\
+                 Terminal=false\n\
+                 X-GNOME-Autostart-enabled=true\n",
+                exec.join(" ")
+            ))
+        }
+    }
+
+    impl Autostart for XdgAutostart {
+        fn is_enabled(&self) -> Result<bool, String> {
+            let content = match fs::read_to_string(&self.path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+                Err(e) => return Err(e.to_string()),
+            };
+
+            // Desktop environments disable entries without deleting them
+            let disabled = content.lines().any(|line| {
+                let line = line.trim();
+                line == "Hidden=true" || line == "X-GNOME-Autostart-enabled=false"
+            });
+            Ok(!disabled)
+        }
+
+        fn enable(&self) -> Result<(), String> {
+            let entry = Self::desktop_entry()?;
+            if fs::read_to_string(&self.path).ok().as_deref() == Some(entry.as_str()) {
+                return Ok(());
+            }
+
+            if let Some(dir) = self.path.parent() {
+                fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            fs_util::write_atomic(&self.path, entry.as_bytes()).map_err(|e| e.to_string())?;
+            log::info!("Registered autostart entry {}", self.path.display());
+            Ok(())
+        }
+
+        fn disable(&self) -> Result<(), String> {
+            match fs::remove_file(&self.path) {
+                Ok(()) => {
+                    log::info!("Removed autostart entry {}", self.path.display());
+                    Ok(())
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+    }
+
+    /// Quote an argument for an `Exec=` key as the desktop entry spec requires
+    fn quote_exec_arg(arg: &str) -> String {
+        let needs_quotes = arg.chars().any(|c| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '"' | '\'' | '\\' | '>' | '<' | '~' | '|' | '&' | ';' | '$' | '*' | '?'
+                        | '#' | '(' | ')' | '`'
+                )
+        });
+
+        let escaped = if needs_quotes {
+            let mut quoted = String::from("\"");
+            for c in arg.chars() {
+                if matches!(c, '"' | '`' | '$' | '\\') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted.push('"');
+            quoted
+        } else {
+            arg.to_string()
+        };
+
+        // The string-value escape applies on top of quoting, and a literal
+        // percent sign would otherwise start a field code
+        escaped.replace('\\', "\\\\").replace('%', "%%")
+    }
+}
@@ -1,4 +1,4 @@
-//! Local file classifier for offline mode.
+//! Local file classification by extension.
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -15,7 +15,6 @@ pub struct LocalRule {
 
 #[derive(Debug, Clone)]
 pub struct LocalClassifier {
-    rules: Vec<LocalRule>,
     extension_map: HashMap<String, String>,
 }
 
@@ -58,10 +57,7 @@ impl Default for LocalClassifier {
             extension_map.insert(ext.to_string(), "Code".to_string());
         }
 
-        Self {
-            rules: Vec::new(),
-            extension_map,
-        }
+        Self { extension_map }
     }
 }
 
@@ -70,81 +66,10 @@ impl LocalClassifier {
         Self::default()
     }
 
-    pub fn set_rules(&mut self, rules: Vec<LocalRule>) {
-        self.rules = rules;
-        self.rules.sort_by(|a, b| b.priority.cmp(&a.priority));
-    }
-
-    pub fn classify(&self, filename: &str, extension: &str) -> Option<(String, String, f64)> {
-        // First try user rules
-        for rule in &self.rules {
-            if self.matches_rule(rule, filename, extension) {
-                return Some((
-                    rule.name.clone(),
-                    rule.destination.clone(),
-                    1.0,
-                ));
-            }
-        }
-
-        // Fall back to extension-based classification
-        if let Some(category) = self.extension_map.get(&extension.to_lowercase()) {
-            return Some((
-                category.clone(),
-                category.clone(),
-                0.8,
-            ));
-        }
-
-        // Default to "Other"
-        Some(("Other".to_string(), "Other".to_string(), 0.5))
-    }
-
-    fn matches_rule(&self, rule: &LocalRule, filename: &str, extension: &str) -> bool {
-        match rule.condition_type.as_str() {
-            "extension" => {
-                if let Some(extensions) = rule.condition_value.get("extensions") {
-                    if let Some(arr) = extensions.as_array() {
-                        return arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .any(|e| e.eq_ignore_ascii_case(extension));
-                    }
-                }
-                false
-            }
-            "keyword" => {
-                if let Some(keywords) = rule.condition_value.get("keywords") {
-                    if let Some(arr) = keywords.as_array() {
-                        let case_sensitive = rule.condition_value
-                            .get("case_sensitive")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
-                        
-                        let check_filename = if case_sensitive {
-                            filename.to_string()
-                        } else {
-                            filename.to_lowercase()
-                        };
-
-                        return arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .any(|k| {
-                                let keyword = if case_sensitive { k.to_string() } else { k.to_lowercase() };
-                                check_filename.contains(&keyword)
-                            });
-                    }
-                }
-                false
-            }
-            "regex" => {
-                if let Some(pattern) = rule.condition_value.get("pattern").and_then(|v| v.as_str()) {
-                    if let Ok(re) = regex::Regex::new(pattern) {
-                        return re.is_match(filename);
-                    }
-                }
-                false
-            }
-            _ => false,
-        }
+    /// Category from the built-in extension map
+    pub fn category_for_extension(&self, extension: &str) -> Option<&str> {
+        self.extension_map
+            .get(&extension.to_lowercase())
+            .map(|c| c.as_str())
     }
 }
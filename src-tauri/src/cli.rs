@@ -0,0 +1,222 @@
+//! Command-line arguments.
+
+use chrono::NaiveDate;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::duplicates::DuplicateAction;
+use crate::export::{ExportFormat, ExportKind};
+
+#[derive(Debug, Default, Parser)]
+#[command(name = "filesorter", version, about = "FileSorter desktop agent")]
+pub struct Cli {
+    /// Start hidden in the tray, as done when launched on login
+    #[arg(long)]
+    pub minimized: bool,
+
+    /// Sort the files in this folder once; passed on to a running agent
+    #[arg(long, value_name = "DIR")]
+    pub organize: Option<PathBuf>,
+
+    /// Directory holding config.json and local data
+    #[arg(long, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Override the API server URL
+    #[arg(long, value_name = "URL")]
+    pub api_url: Option<String>,
+
+    /// Override the web dashboard URL
+    #[arg(long, value_name = "URL")]
+    pub dashboard_url: Option<String>,
+
+    /// Watch this folder instead of the configured ones (repeatable)
+    #[arg(long, value_name = "FOLDER")]
+    pub watch: Vec<String>,
+
+    /// Override the delay before a new file is processed
+    #[arg(long, value_name = "SECONDS")]
+    pub processing_delay: Option<u64>,
+
+    /// Override what happens to duplicate files
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub duplicate_action: Option<DuplicateAction>,
+
+    /// Run a command without the GUI and print JSON
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Watch the configured folders in the foreground
+    Watch,
+
+    /// Sort the files in a folder once
+    Organize {
+        dir: PathBuf,
+
+        /// Only print where each file would go
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Move the most recently sorted file back
+    Undo,
+
+    /// Print the agent's configuration and state
+    Status,
+
+    /// Sign in to the API server
+    Login {
+        #[arg(long)]
+        email: String,
+
+        /// Read from FILESORTER_PASSWORD or prompted for when omitted
+        #[arg(long)]
+        password: Option<String>,
+    },
+
+    /// Send a JSON-RPC request to the running agent, e.g. `ctl pause '{"minutes": 15}'`
+    Ctl {
+        /// status, pause, resume, undo, organize or reload_config
+        method: String,
+
+        /// Parameters as a JSON object
+        params: Option<String>,
+    },
+
+    /// Write the move history or a summary report to a file
+    Export {
+        #[arg(value_enum)]
+        kind: ExportKind,
+
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+
+        /// First local date to include, YYYY-MM-DD
+        #[arg(long)]
+        from: Option<NaiveDate>,
+
+        /// Last local date to include, YYYY-MM-DD
+        #[arg(long)]
+        to: Option<NaiveDate>,
+
+        #[arg(long, short, value_name = "FILE")]
+        output: PathBuf,
+    },
+
+    /// Manage sorting rules
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RulesCommand {
+    /// Download the rules of the active profile into the local cache
+    Sync,
+}
+
+impl Cli {
+    /// Parse the process arguments.
+    ///
+    /// `--help` and `--version` print and exit, as do errors in a subcommand.
+    /// Other parse errors are logged and ignored, since the GUI may be
+    /// launched with platform arguments or a file from a desktop launcher.
+    pub fn parse_lenient() -> Self {
+        let (args, has_subcommand) = Self::without_stray_positionals(std::env::args_os());
+
+        match Self::try_parse_from(args) {
+            Ok(cli) => cli,
+            Err(e)
+                if has_subcommand
+                    || matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) =>
+            {
+                e.exit()
+            }
+            Err(e) => {
+                log::warn!("Ignoring command-line arguments: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Drop positional arguments that are neither a subcommand nor a flag's
+    /// value, and report whether a subcommand was found.
+    pub fn without_stray_positionals(args: impl IntoIterator<Item = OsString>) -> (Vec<OsString>, bool) {
+        let command = Self::command();
+        let takes_value = |flag: &str| {
+            command
+                .get_arguments()
+                .any(|arg| arg.get_long() == Some(flag) && arg.get_action().takes_values())
+        };
+
+        let mut kept = Vec::new();
+        let mut value_pending = false;
+        let mut args = args.into_iter();
+
+        if let Some(program) = args.next() {
+            kept.push(program);
+        }
+
+        while let Some(arg) = args.next() {
+            let text = arg.to_string_lossy().into_owned();
+            if value_pending {
+                value_pending = false;
+            } else if let Some(flag) = text.strip_prefix("--") {
+                value_pending = !flag.is_empty() && !flag.contains('=') && takes_value(flag);
+            } else if text.starts_with('-') && text.len() > 1 {
+                // Short flags at this level never take a value
+            } else if text == "help" || command.find_subcommand(&text).is_some() {
+                // Everything after a subcommand is for the subcommand to parse
+                kept.push(arg);
+                kept.extend(args);
+                return (kept, true);
+            } else {
+                log::warn!("Ignoring command-line argument {:?}", text);
+                continue;
+            }
+            kept.push(arg);
+        }
+
+        (kept, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lenient(args: &[&str]) -> (Vec<String>, bool) {
+        let (kept, has_subcommand) =
+            Cli::without_stray_positionals(args.iter().map(OsString::from));
+        let kept = kept.iter().map(|a| a.to_string_lossy().into_owned()).collect();
+        (kept, has_subcommand)
+    }
+
+    #[test]
+    fn drops_files_passed_by_launchers() {
+        assert_eq!(
+            lenient(&["filesorter", "/home/me/report.pdf", "--minimized"]),
+            (vec!["filesorter".to_string(), "--minimized".to_string()], false)
+        );
+    }
+
+    #[test]
+    fn keeps_flag_values() {
+        let args = ["filesorter", "--config-dir", "cfg", "--watch=/tmp/a", "--organize", "/tmp/b", "stray"];
+        let (kept, has_subcommand) = lenient(&args);
+        assert_eq!(kept, &args[..6]);
+        assert!(!has_subcommand);
+    }
+
+    #[test]
+    fn leaves_subcommands_to_clap() {
+        let args = ["filesorter", "--config-dir", "cfg", "organize", "/tmp/b", "--dry-run"];
+        assert_eq!(lenient(&args), (args.iter().map(|a| a.to_string()).collect(), true));
+        assert!(lenient(&["filesorter", "help"]).1);
+    }
+}
@@ -1,11 +1,78 @@
 //! Configuration management for the desktop agent.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::config_overrides::{self, ConfigSource};
+use crate::duplicates::DuplicateAction;
+use crate::fs_util;
+use std::collections::BTreeMap;
+
+/// Name of the profile every config starts with
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Schema version written by this build
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+
+/// Upgrades for older config shapes. Entry `i` turns version `i` into `i + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v0_structured_folders];
+
+/// Details about a config file that could not be read and was replaced
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRecovery {
+    /// Why the primary config file was rejected
+    pub reason: String,
+    /// Backup the config was restored from, if any
+    pub restored_from: Option<String>,
+    /// Where the unreadable file was moved to
+    pub corrupt_copy: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub enabled: bool,
+}
+
+impl Default for WatchedFolder {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl WatchedFolder {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Settings that differ between named profiles
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub api_url: String,
+    pub dashboard_url: String,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub watched_folders: Vec<WatchedFolder>,
+    pub approved_roots: Vec<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    /// Schema version of the stored file
+    pub config_version: u32,
+
     /// API server URL
     pub api_url: String,
     
@@ -19,7 +86,10 @@ pub struct AppConfig {
     pub refresh_token: Option<String>,
     
     /// List of folders to watch for new files
-    pub watched_folders: Vec<String>,
+    pub watched_folders: Vec<WatchedFolder>,
+
+    /// Folders the user explicitly picked through a native dialog
+    pub approved_roots: Vec<String>,
     
     /// Show desktop notifications
     pub show_notifications: bool,
@@ -29,6 +99,22 @@ pub struct AppConfig {
     
     /// Delay in seconds before processing new file
     pub processing_delay_seconds: u64,
+
+    /// What to do with new files that duplicate already sorted ones
+    pub duplicate_action: DuplicateAction,
+
+    /// Ask before sorting files that arrived during a pause
+    pub review_paused_files: bool,
+
+    /// Name of the profile whose settings are in the fields above
+    pub active_profile: String,
+
+    /// Inactive profiles, keyed by name
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Fields written by newer versions, kept so saving does not drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 impl Default for AppConfig {
@@ -38,49 +124,367 @@ impl Default for AppConfig {
             .unwrap_or_default();
 
         Self {
+            config_version: CURRENT_CONFIG_VERSION,
             api_url: "http://localhost:8085".to_string(),
             dashboard_url: "http://localhost:3000".to_string(),
             access_token: None,
             refresh_token: None,
-            watched_folders: vec![downloads],
+            watched_folders: vec![WatchedFolder::new(downloads)],
+            approved_roots: Vec::new(),
             show_notifications: true,
             start_on_boot: false,
             processing_delay_seconds: 3,
+            duplicate_action: DuplicateAction::default(),
+            review_paused_files: false,
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::new(),
+            extra: Map::new(),
         }
     }
 }
 
 impl AppConfig {
-    /// Get the config file path
-    fn config_path() -> PathBuf {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("filesorter");
-        
+    /// Directory for config.json and local data
+    ///
+    /// `--config-dir` or `FILESORTER_CONFIG_DIR` take precedence over the
+    /// platform config directory.
+    pub fn config_dir() -> PathBuf {
+        let config_dir = config_overrides::config_dir().unwrap_or_else(|| {
+            dirs::config_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("filesorter")
+        });
+
         fs::create_dir_all(&config_dir).ok();
-        config_dir.join("config.json")
+        config_dir
+    }
+
+    /// Get the config file path
+    pub fn config_path() -> PathBuf {
+        Self::config_dir().join("config.json")
+    }
+
+    fn backup_path() -> PathBuf {
+        Self::config_path().with_extension("json.bak")
+    }
+
+    /// Read a config file and layer the env and CLI overrides on top
+    fn read_from(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_object(Self::read_object(path)?)
+    }
+
+    /// Read a config file as stored, upgraded to the current version
+    fn read_object(path: &Path) -> Result<Map<String, Value>, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    /// Deserialize a stored config, then layer the overrides on top
+    ///
+    /// Only the stored config can fail here. Overrides that do not fit are
+    /// dropped with a warning, so they never make a good file look corrupt.
+    fn from_object(object: Map<String, Value>) -> Result<Self, Box<dyn std::error::Error>> {
+        let stored: Self = serde_json::from_value(Value::Object(object.clone()))?;
+
+        let mut layered = object;
+        config_overrides::apply(&mut layered);
+        match serde_json::from_value(Value::Object(layered)) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                log::warn!("Ignoring config overrides that do not apply: {}", e);
+                Ok(stored)
+            }
+        }
+    }
+
+    /// Keep the effective value of every overridden field from `current`
+    ///
+    /// The UI cannot change a field while the environment or command line
+    /// controls it, so edits to such fields are discarded.
+    pub fn keep_overridden(&mut self, current: &Self) -> Result<(), Box<dyn std::error::Error>> {
+        let keys = config_overrides::overridden_keys();
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let (Value::Object(mut object), Value::Object(current)) =
+            (serde_json::to_value(&*self)?, serde_json::to_value(current)?)
+        else {
+            return Err("config did not serialize to an object".into());
+        };
+        for key in keys {
+            if let Some(value) = current.get(&key) {
+                object.insert(key, value.clone());
+            }
+        }
+
+        *self = serde_json::from_value(Value::Object(object))?;
+        Ok(())
+    }
+
+    /// Read the config file as it is on disk, without any recovery
+    pub fn read_current() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::read_from(&Self::config_path())
+    }
+
+    /// Parse a config file of any known version, upgrading it as needed
+    fn parse(content: &str) -> Result<Map<String, Value>, Box<dyn std::error::Error>> {
+        let value: Value = serde_json::from_str(content)?;
+        let Value::Object(mut object) = value else {
+            return Err("config root is not an object".into());
+        };
+
+        let version = object
+            .get("config_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize;
+
+        if version > CURRENT_CONFIG_VERSION as usize {
+            log::warn!(
+                "Config version {} is newer than supported {}, unknown fields are kept as-is",
+                version,
+                CURRENT_CONFIG_VERSION
+            );
+        }
+
+        for (from, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            migration(&mut object);
+            object.insert("config_version".to_string(), Value::from(from + 1));
+            log::info!("Migrated config from version {} to {}", from, from + 1);
+        }
+
+        Ok(object)
+    }
+
+    /// Where each top-level field's effective value comes from
+    pub fn sources(&self) -> BTreeMap<String, ConfigSource> {
+        let effective = match serde_json::to_value(self) {
+            Ok(Value::Object(object)) => object,
+            _ => Map::new(),
+        };
+        let file = Self::read_object(&Self::config_path()).ok();
+
+        config_overrides::sources(&effective, file.as_ref())
+    }
+
+    /// Snapshot of the active profile's settings
+    pub fn current_profile(&self) -> Profile {
+        Profile {
+            api_url: self.api_url.clone(),
+            dashboard_url: self.dashboard_url.clone(),
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone(),
+            watched_folders: self.watched_folders.clone(),
+            approved_roots: self.approved_roots.clone(),
+        }
+    }
+
+    /// Make `name` the active profile, stashing the current one
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        if name == self.active_profile {
+            return Ok(());
+        }
+
+        let profile = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| format!("Unknown profile: {}", name))?;
+
+        let previous = std::mem::replace(&mut self.active_profile, name.to_string());
+        self.profiles.insert(previous, self.current_profile());
+
+        self.api_url = profile.api_url;
+        self.dashboard_url = profile.dashboard_url;
+        self.access_token = profile.access_token;
+        self.refresh_token = profile.refresh_token;
+        self.watched_folders = profile.watched_folders;
+        self.approved_roots = profile.approved_roots;
+        Ok(())
+    }
+
+    /// Add a profile, either with default settings or copied from `source`
+    ///
+    /// Copies never carry credentials, so each profile signs in on its own.
+    pub fn create_profile(&mut self, name: &str, source: Option<&str>) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name is empty".to_string());
+        }
+        if name.chars().count() > 64 {
+            return Err("Profile name must not exceed 64 characters".to_string());
+        }
+        if self.profile(name).is_some() {
+            return Err(format!("Profile already exists: {}", name));
+        }
+
+        let profile = match source {
+            Some(source) => Profile {
+                access_token: None,
+                refresh_token: None,
+                ..self
+                    .profile(source)
+                    .ok_or_else(|| format!("Unknown profile: {}", source))?
+            },
+            None => Self::default().current_profile(),
+        };
+
+        self.profiles.insert(name.to_string(), profile);
+        Ok(())
+    }
+
+    /// Names of all profiles, the active one included
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        names.push(self.active_profile.clone());
+        names.sort();
+        names
+    }
+
+    /// Settings of any profile by name
+    pub fn profile(&self, name: &str) -> Option<Profile> {
+        if name == self.active_profile {
+            Some(self.current_profile())
+        } else {
+            self.profiles.get(name).cloned()
+        }
+    }
+
+    /// Copy of the config with every credential removed, safe for the webview
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        config.access_token = None;
+        config.refresh_token = None;
+        for profile in config.profiles.values_mut() {
+            profile.access_token = None;
+            profile.refresh_token = None;
+        }
+        config
+    }
+
+    /// Folders the watcher should currently monitor
+    pub fn enabled_folders(&self) -> Vec<String> {
+        self.watched_folders
+            .iter()
+            .filter(|f| f.enabled)
+            .map(|f| f.path.clone())
+            .collect()
     }
 
     /// Load configuration from file
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+    ///
+    /// If the file is unreadable, the last good backup is used instead and
+    /// the returned `ConfigRecovery` describes what happened.
+    pub fn load() -> Result<(Self, Option<ConfigRecovery>), Box<dyn std::error::Error>> {
         let path = Self::config_path();
         
         if !path.exists() {
-            let config = Self::default();
-            config.save()?;
-            return Ok(config);
+            Self::default().save()?;
         }
 
-        let content = fs::read_to_string(&path)?;
-        let config: Self = serde_json::from_str(&content)?;
-        Ok(config)
+        let error = match Self::read_from(&path) {
+            Ok(config) => return Ok((config, None)),
+            Err(e) => e,
+        };
+
+        log::error!("Config file {} is unreadable: {}", path.display(), error);
+
+        // Keep the broken file for inspection instead of overwriting it
+        let corrupt_path = path.with_extension(format!(
+            "json.corrupt-{}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let corrupt_copy = fs::rename(&path, &corrupt_path)
+            .ok()
+            .map(|_| corrupt_path.to_string_lossy().to_string());
+
+        let backup_path = Self::backup_path();
+        let (config, restored_from) = match Self::read_from(&backup_path) {
+            Ok(config) => {
+                log::warn!("Restored config from {}", backup_path.display());
+                (config, Some(backup_path.to_string_lossy().to_string()))
+            }
+            Err(e) => {
+                log::warn!("No usable config backup ({}), using defaults", e);
+                (Self::from_object(Map::new())?, None)
+            }
+        };
+
+        config.save()?;
+
+        Ok((
+            config,
+            Some(ConfigRecovery {
+                reason: error.to_string(),
+                restored_from,
+                corrupt_copy,
+            }),
+        ))
+    }
+
+    /// Change the config as stored on disk and return the new effective config
+    ///
+    /// Unlike `save`, this bypasses env and CLI overrides entirely, so it can
+    /// rewrite fields that happen to be overridden in this session.
+    pub fn update_stored<F>(update: F) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&mut Self) -> Result<(), String>,
+    {
+        let path = Self::config_path();
+        let mut stored: Self = match Self::read_object(&path) {
+            Ok(object) => serde_json::from_value(Value::Object(object))?,
+            Err(_) => Self::default(),
+        };
+
+        update(&mut stored)?;
+
+        let content = serde_json::to_string_pretty(&stored)?;
+        Self::write(&path, &content)?;
+        Self::read_current()
     }
 
     /// Save configuration to file
+    ///
+    /// The previous file is kept as a backup if it is still valid, and the
+    /// new one is written atomically. Fields overridden from the environment
+    /// or command line keep their on-disk values.
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let path = Self::config_path();
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+        let on_disk = Self::read_object(&path).ok();
+
+        let mut object = match serde_json::to_value(self)? {
+            Value::Object(object) => object,
+            _ => return Err("config did not serialize to an object".into()),
+        };
+        config_overrides::strip(&mut object, on_disk.as_ref());
+        let content = serde_json::to_string_pretty(&object)?;
+
+        Self::write(&path, &content)
+    }
+
+    /// Back up the current file if it still loads, then replace it
+    fn write(path: &Path, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let on_disk_valid = Self::read_object(path)
+            .ok()
+            .map(|object| serde_json::from_value::<Self>(Value::Object(object)).is_ok())
+            .unwrap_or(false);
+        if on_disk_valid {
+            fs::copy(path, Self::backup_path())?;
+        }
+
+        fs_util::write_atomic(path, content.as_bytes())?;
         Ok(())
     }
 }
+
+/// v0 -> v1: `watched_folders` changed from plain strings to objects
+fn migrate_v0_structured_folders(config: &mut Map<String, Value>) {
+    let Some(Value::Array(folders)) = config.get_mut("watched_folders") else {
+        return;
+    };
+
+    for folder in folders.iter_mut() {
+        if let Value::String(path) = folder {
+            *folder = serde_json::json!({ "path": path, "enabled": true });
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Environment and command-line layers on top of config.json.
+//!
+//! Precedence, lowest to highest: built-in defaults, the config file,
+//! `FILESORTER_*` environment variables, command-line flags. Overridden
+//! values are applied in memory only and never written back to the file.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::cli::Cli;
+use crate::duplicates::DuplicateAction;
+
+/// Where the effective value of a config field came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Text,
+    Bool,
+    Number,
+    Folders,
+    DuplicateAction,
+}
+
+/// Config fields that may be overridden, with their environment variable
+const OVERRIDABLE: &[(&str, &str, Kind)] = &[
+    ("api_url", "FILESORTER_API_URL", Kind::Text),
+    ("dashboard_url", "FILESORTER_DASHBOARD_URL", Kind::Text),
+    ("watched_folders", "FILESORTER_WATCHED_FOLDERS", Kind::Folders),
+    ("show_notifications", "FILESORTER_SHOW_NOTIFICATIONS", Kind::Bool),
+    ("start_on_boot", "FILESORTER_START_ON_BOOT", Kind::Bool),
+    ("processing_delay_seconds", "FILESORTER_PROCESSING_DELAY_SECONDS", Kind::Number),
+    ("duplicate_action", "FILESORTER_DUPLICATE_ACTION", Kind::DuplicateAction),
+    ("review_paused_files", "FILESORTER_REVIEW_PAUSED_FILES", Kind::Bool),
+];
+
+/// Environment variable that relocates config.json and local data
+const CONFIG_DIR_ENV: &str = "FILESORTER_CONFIG_DIR";
+
+#[derive(Debug, Default)]
+pub struct ConfigOverrides {
+    config_dir: Option<PathBuf>,
+    env: Map<String, Value>,
+    cli: Map<String, Value>,
+}
+
+static OVERRIDES: OnceLock<ConfigOverrides> = OnceLock::new();
+
+/// Collect overrides from the environment and `cli`. Call once at startup.
+pub fn init(cli: &Cli) {
+    let overrides = ConfigOverrides {
+        config_dir: cli
+            .config_dir
+            .clone()
+            .or_else(|| std::env::var_os(CONFIG_DIR_ENV).map(PathBuf::from)),
+        env: env_layer(),
+        cli: cli_layer(cli),
+    };
+
+    if OVERRIDES.set(overrides).is_err() {
+        log::warn!("Config overrides were already initialized");
+    }
+}
+
+fn get() -> &'static ConfigOverrides {
+    OVERRIDES.get_or_init(ConfigOverrides::default)
+}
+
+/// Directory override for config.json and local data, if any
+pub fn config_dir() -> Option<PathBuf> {
+    get().config_dir.clone()
+}
+
+/// Apply the environment and CLI layers to a config object.
+pub fn apply(config: &mut Map<String, Value>) {
+    get().apply(config);
+}
+
+/// Fields whose value currently comes from the environment or command line
+pub fn overridden_keys() -> Vec<String> {
+    let overrides = get();
+    overrides.env.keys().chain(overrides.cli.keys()).cloned().collect()
+}
+
+impl ConfigOverrides {
+    fn apply(&self, config: &mut Map<String, Value>) {
+        for (key, value) in self.env.iter().chain(self.cli.iter()) {
+            config.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Replace overridden fields in `effective` with their file-layer values.
+///
+/// `file` is the config as stored on disk; fields missing there are removed
+/// so the built-in default applies again on the next load.
+pub fn strip(effective: &mut Map<String, Value>, file: Option<&Map<String, Value>>) {
+    let overrides = get();
+    for key in overrides.env.keys().chain(overrides.cli.keys()) {
+        match file.and_then(|f| f.get(key)) {
+            Some(value) => {
+                effective.insert(key.clone(), value.clone());
+            }
+            None => {
+                effective.remove(key);
+            }
+        }
+    }
+}
+
+/// Source of every top-level field, given the keys present in the file.
+pub fn sources(
+    effective: &Map<String, Value>,
+    file: Option<&Map<String, Value>>,
+) -> BTreeMap<String, ConfigSource> {
+    let overrides = get();
+
+    effective
+        .keys()
+        .map(|key| {
+            let source = if overrides.cli.contains_key(key) {
+                ConfigSource::Cli
+            } else if overrides.env.contains_key(key) {
+                ConfigSource::Env
+            } else if file.map(|f| f.contains_key(key)).unwrap_or(false) {
+                ConfigSource::File
+            } else {
+                ConfigSource::Default
+            };
+            (key.clone(), source)
+        })
+        .collect()
+}
+
+fn env_layer() -> Map<String, Value> {
+    let mut layer = Map::new();
+
+    for (field, var, kind) in OVERRIDABLE {
+        let Ok(raw) = std::env::var(var) else {
+            continue;
+        };
+
+        match parse(&raw, *kind) {
+            Some(value) => {
+                log::info!("Config field {} overridden by {}", field, var);
+                layer.insert(field.to_string(), value);
+            }
+            None => log::warn!("Ignoring invalid value for {}: {:?}", var, raw),
+        }
+    }
+
+    layer
+}
+
+fn cli_layer(cli: &Cli) -> Map<String, Value> {
+    let mut layer = Map::new();
+
+    if let Some(url) = &cli.api_url {
+        layer.insert("api_url".to_string(), Value::from(url.clone()));
+    }
+    if let Some(url) = &cli.dashboard_url {
+        layer.insert("dashboard_url".to_string(), Value::from(url.clone()));
+    }
+    if !cli.watch.is_empty() {
+        layer.insert("watched_folders".to_string(), folders_value(cli.watch.iter().cloned()));
+    }
+    if let Some(delay) = cli.processing_delay {
+        layer.insert("processing_delay_seconds".to_string(), Value::from(delay));
+    }
+    if let Some(action) = cli.duplicate_action {
+        if let Ok(value) = serde_json::to_value(action) {
+            layer.insert("duplicate_action".to_string(), value);
+        }
+    }
+
+    layer
+}
+
+fn parse(raw: &str, kind: Kind) -> Option<Value> {
+    match kind {
+        Kind::Text => Some(Value::from(raw)),
+        Kind::Number => raw.trim().parse::<u64>().ok().map(Value::from),
+        Kind::Bool => match raw.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(Value::Bool(true)),
+            "0" | "false" | "no" | "off" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Kind::Folders => Some(folders_value(
+            std::env::split_paths(raw).map(|p| p.to_string_lossy().to_string()),
+        )),
+        Kind::DuplicateAction => {
+            let action: DuplicateAction = serde_json::from_value(Value::from(raw.trim())).ok()?;
+            serde_json::to_value(action).ok()
+        }
+    }
+}
+
+fn folders_value(folders: impl Iterator<Item = String>) -> Value {
+    Value::Array(
+        folders
+            .filter(|f| !f.is_empty())
+            .map(|path| serde_json::json!({ "path": path, "enabled": true }))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use serde_json::json;
+
+    #[test]
+    fn parses_typed_env_values() {
+        assert_eq!(parse(" 7 ", Kind::Number), Some(json!(7)));
+        assert_eq!(parse("-1", Kind::Number), None);
+        assert_eq!(parse("Yes", Kind::Bool), Some(json!(true)));
+        assert_eq!(parse("off", Kind::Bool), Some(json!(false)));
+        assert_eq!(parse("maybe", Kind::Bool), None);
+        assert_eq!(parse("trash", Kind::DuplicateAction), Some(json!("trash")));
+        assert_eq!(parse(" move_to_duplicates", Kind::DuplicateAction), Some(json!("move_to_duplicates")));
+        assert_eq!(parse("foo", Kind::DuplicateAction), None);
+    }
+
+    #[test]
+    fn cli_flags_become_a_layer() {
+        let cli = Cli::try_parse_from([
+            "filesorter",
+            "--watch",
+            "/tmp/a",
+            "--processing-delay",
+            "9",
+            "--duplicate-action",
+            "hard_link",
+        ])
+        .unwrap();
+        let layer = cli_layer(&cli);
+
+        assert_eq!(layer["watched_folders"], json!([{ "path": "/tmp/a", "enabled": true }]));
+        assert_eq!(layer["processing_delay_seconds"], json!(9));
+        assert_eq!(layer["duplicate_action"], json!("hard_link"));
+        assert!(!layer.contains_key("api_url"));
+
+        assert!(Cli::try_parse_from(["filesorter", "--duplicate-action", "foo"]).is_err());
+    }
+
+    #[test]
+    fn cli_layer_wins_over_env_layer() {
+        let overrides = ConfigOverrides {
+            config_dir: None,
+            env: Map::from_iter([
+                ("api_url".to_string(), json!("http://env")),
+                ("start_on_boot".to_string(), json!(true)),
+            ]),
+            cli: Map::from_iter([("api_url".to_string(), json!("http://cli"))]),
+        };
+
+        let mut config = Map::from_iter([
+            ("api_url".to_string(), json!("http://file")),
+            ("show_notifications".to_string(), json!(false)),
+        ]);
+        overrides.apply(&mut config);
+
+        assert_eq!(config["api_url"], json!("http://cli"));
+        assert_eq!(config["start_on_boot"], json!(true));
+        assert_eq!(config["show_notifications"], json!(false));
+    }
+}
@@ -0,0 +1,152 @@
+//! Validation of `AppConfig` values before they are saved or used.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// Longest delay we accept before a new file is processed
+const MAX_PROCESSING_DELAY_SECONDS: u64 = 3600;
+
+/// Delays above this still work but make the agent feel unresponsive
+const SLOW_PROCESSING_DELAY_SECONDS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldIssue {
+    /// Dotted path of the offending field, e.g. `watched_folders[1].path`
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<FieldIssue>,
+    pub warnings: Vec<FieldIssue>,
+}
+
+impl ValidationReport {
+    /// Report holding a single error, for failures outside field checks
+    pub fn failed(field: impl Into<String>, message: impl Into<String>) -> Self {
+        let mut report = Self::default();
+        report.error(field, message);
+        report
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldIssue {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(FieldIssue {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+}
+
+/// Check every field of `config` and collect errors and warnings.
+pub fn validate(config: &AppConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    validate_url(&mut report, "api_url", &config.api_url);
+    validate_url(&mut report, "dashboard_url", &config.dashboard_url);
+    validate_watched_folders(&mut report, config);
+
+    let delay = config.processing_delay_seconds;
+    if delay == 0 {
+        report.error(
+            "processing_delay_seconds",
+            "Delay must be at least 1 second so files finish writing",
+        );
+    } else if delay > MAX_PROCESSING_DELAY_SECONDS {
+        report.error(
+            "processing_delay_seconds",
+            format!("Delay must not exceed {} seconds", MAX_PROCESSING_DELAY_SECONDS),
+        );
+    } else if delay > SLOW_PROCESSING_DELAY_SECONDS {
+        report.warning(
+            "processing_delay_seconds",
+            "Files will wait more than 5 minutes before being sorted",
+        );
+    }
+
+    report
+}
+
+fn validate_url(report: &mut ValidationReport, field: &str, value: &str) {
+    let url = match reqwest::Url::parse(value) {
+        Ok(url) => url,
+        Err(e) => {
+            report.error(field, format!("Invalid URL: {}", e));
+            return;
+        }
+    };
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        report.error(field, "URL must use http or https");
+        return;
+    }
+
+    let Some(host) = url.host_str() else {
+        report.error(field, "URL must include a host");
+        return;
+    };
+
+    let is_local = host == "localhost" || host == "127.0.0.1" || host == "[::1]";
+    if url.scheme() == "http" && !is_local {
+        report.warning(field, "Unencrypted http is only safe for local servers");
+    }
+}
+
+fn validate_watched_folders(report: &mut ValidationReport, config: &AppConfig) {
+    if config.watched_folders.is_empty() {
+        report.warning("watched_folders", "No folders are being watched");
+    }
+
+    // Canonical paths of folders that passed the per-folder checks
+    let mut seen: Vec<(usize, PathBuf)> = Vec::new();
+
+    for (i, folder) in config.watched_folders.iter().enumerate() {
+        let field = format!("watched_folders[{}].path", i);
+
+        if folder.path.trim().is_empty() {
+            report.error(field, "Folder path is empty");
+            continue;
+        }
+
+        let path = Path::new(&folder.path);
+        if !path.exists() {
+            report.error(field, format!("Folder does not exist: {}", folder.path));
+            continue;
+        }
+        if !path.is_dir() {
+            report.error(field, format!("Not a folder: {}", folder.path));
+            continue;
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        for (other_index, other) in &seen {
+            if *other == canonical {
+                report.error(
+                    field.clone(),
+                    format!("Duplicate of watched_folders[{}]", other_index),
+                );
+            } else if canonical.starts_with(other) || other.starts_with(&canonical) {
+                report.error(
+                    field.clone(),
+                    format!("Nested with watched_folders[{}]", other_index),
+                );
+            }
+        }
+
+        seen.push((i, canonical));
+    }
+}
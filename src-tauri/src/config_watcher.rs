@@ -0,0 +1,90 @@
+//! Picks up edits to config.json made outside the app.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Manager};
+
+use crate::config::AppConfig;
+use crate::config_validation;
+use crate::AppState;
+
+/// Quiet period after a change before the file is re-read
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watch config.json and apply valid external edits to the running app
+pub fn spawn(app: AppHandle) {
+    std::thread::spawn(move || {
+        let path = AppConfig::config_path();
+        let Some(dir) = path.parent() else {
+            return;
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::error!("Cannot watch config file: {}", e);
+                return;
+            }
+        };
+
+        // Watch the directory, since editors often replace the file on save
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            log::error!("Cannot watch config directory {}: {}", dir.display(), e);
+            return;
+        }
+
+        while let Ok(res) = rx.recv() {
+            let Ok(event) = res else {
+                continue;
+            };
+            if !event.paths.iter().any(|p| p == &path) {
+                continue;
+            }
+
+            // Let the writer finish and collapse bursts of events
+            std::thread::sleep(DEBOUNCE);
+            while rx.try_recv().is_ok() {}
+
+            if let Err(e) = reload(&app) {
+                log::warn!("Ignoring config.json edit: {}", e);
+            }
+        }
+    });
+}
+
+/// Re-read config.json and apply it if valid and changed.
+///
+/// Returns whether anything changed.
+pub(crate) fn reload(app: &AppHandle) -> Result<bool, String> {
+    let new_config = AppConfig::read_current().map_err(|e| format!("Unreadable config.json: {}", e))?;
+
+    let report = config_validation::validate(&new_config);
+    if let Some(issue) = report.errors.first() {
+        return Err(format!("Invalid config.json, {}: {}", issue.field, issue.message));
+    }
+
+    let state = app.state::<AppState>();
+    {
+        let mut config = state.config.lock().map_err(|e| e.to_string())?;
+
+        // Our own saves land here too; nothing to do when nothing changed
+        if serde_json::to_value(&*config).ok() == serde_json::to_value(&new_config).ok() {
+            return Ok(false);
+        }
+
+        if config.start_on_boot != new_config.start_on_boot {
+            if let Err(e) = crate::autostart::apply(new_config.start_on_boot) {
+                log::warn!("Failed to update autostart registration: {}", e);
+            }
+        }
+        *config = new_config.clone();
+    }
+
+    log::info!("Reloaded config.json");
+    crate::apply_config_to_watcher(app, &new_config);
+    crate::refresh_tray_menu(app);
+    Ok(true)
+}
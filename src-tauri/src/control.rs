@@ -0,0 +1,481 @@
+//! Control socket for scripting a running agent.
+//!
+//! The agent listens on a Unix domain socket (a named pipe on Windows) and
+//! speaks JSON-RPC 2.0, one request per line. Methods:
+//!
+//! - `status`
+//! - `pause`, optionally with `{"minutes": n}` or `{"duration": "one_hour"}`
+//!   for a timed pause
+//! - `resume`
+//! - `undo`
+//! - `organize` with `{"dir": "...", "dry_run": false}`
+//! - `reload_config`
+//! - `forward_args` with `{"args": [...], "cwd": "..."}`, sent by a second
+//!   launch of the app
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::cli::Cli;
+use crate::file_watcher::{self, ProcessContext, WatcherSettings};
+use crate::{config_watcher, pause, undo, AppState};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Method was valid but the agent could not carry it out
+const APP_ERROR: i64 = -32000;
+
+/// How long a caller waits for a quick request to be answered
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Organizing a large folder can take a while before the reply comes back
+pub const ORGANIZE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Start of the error `call` returns when nothing listens on the socket
+pub const NOT_RUNNING: &str = "FileSorter is not running";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+fn rpc_error(code: i64, message: impl Into<String>) -> RpcError {
+    RpcError {
+        code,
+        message: message.into(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PauseParams {
+    minutes: Option<u64>,
+    duration: Option<pause::PauseDuration>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForwardParams {
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrganizeParams {
+    dir: PathBuf,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+fn params<T: serde::de::DeserializeOwned + Default>(params: Value) -> Result<T, RpcError> {
+    if params.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(params).map_err(|e| rpc_error(INVALID_PARAMS, e.to_string()))
+}
+
+async fn dispatch(app: &AppHandle, method: &str, params_value: Value) -> Result<Value, RpcError> {
+    let state = app.state::<AppState>();
+
+    match method {
+        "status" => Ok(crate::status_json(&state)),
+        "pause" => {
+            let PauseParams { minutes, duration } = params(params_value)?;
+            let until = match (minutes, duration) {
+                (Some(m), _) => Some(chrono::Local::now() + chrono::Duration::minutes(m as i64)),
+                (None, Some(duration)) => duration.deadline(),
+                (None, None) => None,
+            };
+            pause::set(app, true, until);
+            Ok(crate::status_json(&state))
+        }
+        "resume" => {
+            pause::set(app, false, None);
+            Ok(crate::status_json(&state))
+        }
+        "undo" => {
+            let mut storage = state.storage.lock().map_err(|e| rpc_error(APP_ERROR, e.to_string()))?;
+            let mut index = state
+                .duplicate_index
+                .lock()
+                .map_err(|e| rpc_error(APP_ERROR, e.to_string()))?;
+            let record = undo::undo_last(&mut storage, &mut index).map_err(|e| rpc_error(APP_ERROR, e))?;
+            drop((storage, index));
+
+            // The undone move no longer counts towards today
+            crate::refresh_tray_menu(app);
+            Ok(json!({ "undone": record }))
+        }
+        "organize" => {
+            let organize: OrganizeParams = serde_json::from_value(params_value)
+                .map_err(|e| rpc_error(INVALID_PARAMS, e.to_string()))?;
+
+            organize_folder(app, &organize.dir, organize.dry_run)
+                .await
+                .map_err(|e| rpc_error(APP_ERROR, e))
+        }
+        "forward_args" => {
+            let forwarded: ForwardParams = serde_json::from_value(params_value)
+                .map_err(|e| rpc_error(INVALID_PARAMS, e.to_string()))?;
+            forwarded_launch(app, forwarded).await
+        }
+        "reload_config" => {
+            let changed = config_watcher::reload(app).map_err(|e| rpc_error(APP_ERROR, e))?;
+            Ok(json!({ "changed": changed }))
+        }
+        _ => Err(rpc_error(METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    }
+}
+
+async fn organize_folder(app: &AppHandle, dir: &std::path::Path, dry_run: bool) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+
+    let settings = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        WatcherSettings::from_config(&config)
+    }
+    .ok_or("Not logged in")?;
+
+    let ctx = ProcessContext {
+        app_handle: Some(app.clone()),
+        storage: state.storage.clone(),
+        duplicate_index: state.duplicate_index.clone(),
+    };
+
+    file_watcher::organize_folder(dir, dry_run, &settings, &ctx).await
+}
+
+/// Sort a folder named on the command line and tell the user how it went
+pub async fn organize_from_launch(app: AppHandle, dir: PathBuf) -> Result<Value, String> {
+    let result = organize_folder(&app, &dir, false).await;
+
+    let body = match &result {
+        Ok(summary) => format!(
+            "{}: отсортировано {}, ошибок {}",
+            dir.display(),
+            summary["sorted"],
+            summary["failed"]
+        ),
+        Err(e) => format!("{}: {}", dir.display(), e),
+    };
+    let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title("FileSorter")
+        .body(body)
+        .show();
+
+    result
+}
+
+/// Act on the arguments of a launch that found this agent already running
+async fn forwarded_launch(app: &AppHandle, forwarded: ForwardParams) -> Result<Value, RpcError> {
+    let args = std::iter::once("filesorter".to_string()).chain(forwarded.args).map(Into::into);
+    let (args, _) = Cli::without_stray_positionals(args);
+    let cli = Cli::try_parse_from(args).map_err(|e| rpc_error(INVALID_PARAMS, e.to_string()))?;
+
+    if !cli.minimized {
+        crate::show_main_window(app);
+    }
+
+    let organized = match cli.organize {
+        Some(dir) => {
+            // Relative paths are relative to where the second launch ran
+            let dir = match forwarded.cwd {
+                Some(cwd) => cwd.join(dir),
+                None => dir,
+            };
+            Some(
+                organize_from_launch(app.clone(), dir)
+                    .await
+                    .map_err(|e| rpc_error(APP_ERROR, e))?,
+            )
+        }
+        None => None,
+    };
+
+    Ok(json!({ "shown": !cli.minimized, "organized": organized }))
+}
+
+async fn handle_line(app: &AppHandle, line: &str) -> Response {
+    let request: Request = match serde_json::from_str::<Value>(line) {
+        Err(e) => return Response::new(Value::Null, Err(rpc_error(PARSE_ERROR, e.to_string()))),
+        Ok(value) => match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => {
+                return Response::new(Value::Null, Err(rpc_error(INVALID_REQUEST, e.to_string())))
+            }
+        },
+    };
+
+    if request.jsonrpc != "2.0" {
+        return Response::new(
+            request.id,
+            Err(rpc_error(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported")),
+        );
+    }
+
+    log::info!("Control request: {}", request.method);
+    let outcome = dispatch(app, &request.method, request.params).await;
+    Response::new(request.id, outcome)
+}
+
+/// Serve requests on one connection until the client hangs up
+async fn serve_connection<S>(app: AppHandle, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_line(&app, &line).await;
+        let Ok(mut bytes) = serde_json::to_vec(&response) else {
+            break;
+        };
+        bytes.push(b'\n');
+        if writer.write_all(&bytes).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Send one request to the running agent and return its result
+///
+/// Gives up after `timeout`, so an agent that accepted the connection but
+/// hangs cannot block the caller forever.
+pub async fn call(method: &str, params: Value, timeout: Duration) -> Result<Value, String> {
+    tokio::time::timeout(timeout, exchange(method, params))
+        .await
+        .map_err(|_| format!("FileSorter did not answer within {} s", timeout.as_secs()))?
+}
+
+async fn exchange(method: &str, params: Value) -> Result<Value, String> {
+    let request = Request {
+        jsonrpc: "2.0".to_string(),
+        id: Value::from(1),
+        method: method.to_string(),
+        params,
+    };
+    let mut bytes = serde_json::to_vec(&request).map_err(|e| e.to_string())?;
+    bytes.push(b'\n');
+
+    let stream = platform::connect()
+        .await
+        .map_err(|e| format!("{} ({})", NOT_RUNNING, e))?;
+    let (reader, mut writer) = tokio::io::split(stream);
+    writer.write_all(&bytes).await.map_err(|e| e.to_string())?;
+
+    let line = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Agent closed the connection")?;
+
+    let response: Response = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(error.message),
+        (Some(result), None) => Ok(result),
+        (None, None) => Ok(Value::Null),
+    }
+}
+
+/// Start serving the control socket in the background
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = platform::serve(app).await {
+            log::error!("Control socket unavailable: {}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::path::PathBuf;
+    use tauri::AppHandle;
+    use tokio::net::{UnixListener, UnixStream};
+
+    use crate::config::AppConfig;
+
+    pub fn socket_path() -> PathBuf {
+        AppConfig::config_dir().join("run").join("control.sock")
+    }
+
+    pub async fn connect() -> std::io::Result<UnixStream> {
+        UnixStream::connect(socket_path()).await
+    }
+
+    pub async fn serve(app: AppHandle) -> std::io::Result<()> {
+        let path = socket_path();
+
+        // A private directory keeps other users out even before the chmod below
+        if let Some(dir) = path.parent() {
+            std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).await.is_ok() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::AddrInUse,
+                        "another agent is already listening",
+                    ));
+                }
+                // Left behind by an agent that did not shut down cleanly
+                std::fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Only the current user may drive the agent
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        log::info!("Control socket listening on {}", path.display());
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            tauri::async_runtime::spawn(super::serve_connection(app.clone(), stream));
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use tauri::AppHandle;
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions};
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows_sys::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+
+    /// Full access for the pipe's owner and nobody else, ignoring inherited entries
+    const OWNER_ONLY_SDDL: &str = "D:P(A;;GA;;;OW)";
+
+    /// Security descriptor restricting the pipe to the user running the agent
+    struct OwnerOnly(PSECURITY_DESCRIPTOR);
+
+    // Safety: the descriptor is plain heap memory that is never mutated
+    unsafe impl Send for OwnerOnly {}
+
+    impl OwnerOnly {
+        fn new() -> std::io::Result<Self> {
+            let sddl: Vec<u16> = std::ffi::OsStr::new(OWNER_ONLY_SDDL)
+                .encode_wide()
+                .chain(Some(0))
+                .collect();
+            let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+
+            // Safety: `sddl` is NUL-terminated and outlives the call
+            let converted = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut descriptor,
+                    std::ptr::null_mut(),
+                )
+            };
+            if converted == 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(Self(descriptor))
+        }
+
+        fn create(&self, options: &ServerOptions, name: &str) -> std::io::Result<NamedPipeServer> {
+            let mut attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: self.0,
+                bInheritHandle: 0,
+            };
+
+            // Safety: `attributes` and the descriptor it points to outlive the call
+            unsafe {
+                options.create_with_security_attributes_raw(name, &mut attributes as *mut _ as *mut c_void)
+            }
+        }
+    }
+
+    impl Drop for OwnerOnly {
+        fn drop(&mut self) {
+            // Safety: allocated with LocalAlloc by the conversion above
+            unsafe { LocalFree(self.0) };
+        }
+    }
+
+    fn pipe_name() -> String {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        format!(r"\\.\pipe\filesorter-{}", user)
+    }
+
+    pub async fn connect() -> std::io::Result<NamedPipeClient> {
+        ClientOptions::new().open(pipe_name())
+    }
+
+    pub async fn serve(app: AppHandle) -> std::io::Result<()> {
+        let name = pipe_name();
+        let security = OwnerOnly::new()?;
+        let mut server = security.create(
+            ServerOptions::new()
+                .first_pipe_instance(true)
+                .reject_remote_clients(true),
+            &name,
+        )?;
+        log::info!("Control pipe listening on {}", name);
+
+        loop {
+            server.connect().await?;
+            let connected = server;
+            server = security.create(ServerOptions::new().reject_remote_clients(true), &name)?;
+            tauri::async_runtime::spawn(super::serve_connection(app.clone(), connected));
+        }
+    }
+}
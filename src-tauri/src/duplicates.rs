@@ -0,0 +1,569 @@
+//! Duplicate file detection by content hash.
+//!
+//! Files are grouped by size first, then compared with a fast hash over the
+//! head and tail of the file. A full-content hash confirms the match before a
+//! file is reported as a duplicate.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::config::AppConfig;
+use crate::fs_util;
+
+/// Bytes sampled from each end of the file for the fast hash
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// How deep `scan_folder` descends into sorted subfolders
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// What to do with a new file that duplicates an already indexed one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Leave the newcomer where it is
+    #[default]
+    Skip,
+    /// Move the newcomer into a "Duplicates" folder next to it
+    MoveToDuplicates,
+    /// Replace the newcomer with a hard link to the existing copy
+    HardLink,
+    /// Send the newcomer to the system trash
+    Trash,
+}
+
+/// Perceptual hashes of an image, see `similar_images`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PerceptualHash {
+    pub average: u64,
+    pub difference: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: i64,
+    pub fast_hash: Option<String>,
+    pub full_hash: Option<String>,
+    #[serde(default)]
+    pub perceptual_hash: Option<PerceptualHash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DuplicateIndex {
+    /// Indexed files keyed by size in bytes
+    files: HashMap<u64, Vec<IndexedFile>>,
+}
+
+impl DuplicateIndex {
+    fn index_path() -> PathBuf {
+        AppConfig::config_dir().join("hash_index.json")
+    }
+
+    pub fn load() -> Self {
+        Self::read(&Self::index_path()).unwrap_or_default()
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Write the index out, keeping what other agents saved in the meantime.
+    ///
+    /// The GUI and the headless CLI share `hash_index.json`, so entries the
+    /// file gained since it was loaded are merged in first, as long as the
+    /// files they describe are still there unchanged.
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&Self::index_path())
+    }
+
+    fn save_to(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(mut on_disk) = Self::read(path) {
+            on_disk.retain_unchanged();
+            self.merge(on_disk);
+        }
+        let content = serde_json::to_string(self)?;
+        fs_util::write_atomic(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Drop entries whose file is gone or no longer matches its size and mtime
+    fn retain_unchanged(&mut self) {
+        for entries in self.files.values_mut() {
+            entries.retain(|e| {
+                fs::metadata(&e.path)
+                    .map(|m| m.len() == e.size && modified_secs(&m) == e.modified)
+                    .unwrap_or(false)
+            });
+        }
+        self.files.retain(|_, entries| !entries.is_empty());
+    }
+
+    /// Add or refresh a file in the index. Hashes are computed lazily.
+    pub fn insert(&mut self, path: &Path) {
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        if !metadata.is_file() || metadata.len() == 0 {
+            return;
+        }
+
+        let path_str = path.to_string_lossy().to_string();
+        self.remove(path);
+
+        self.files.entry(metadata.len()).or_default().push(IndexedFile {
+            path: path_str,
+            size: metadata.len(),
+            modified: modified_secs(&metadata),
+            fast_hash: None,
+            full_hash: None,
+            perceptual_hash: None,
+        });
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &IndexedFile> {
+        self.files.values().flatten()
+    }
+
+    pub fn files_mut(&mut self) -> impl Iterator<Item = &mut IndexedFile> {
+        self.files.values_mut().flatten()
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let path_str = path.to_string_lossy();
+        for entries in self.files.values_mut() {
+            entries.retain(|e| e.path != path_str);
+        }
+        self.files.retain(|_, entries| !entries.is_empty());
+    }
+
+    /// Index every file below `root`, skipping hidden entries.
+    pub fn scan_folder(&mut self, root: &Path) {
+        self.scan_dir(root, 0);
+    }
+
+    fn scan_dir(&mut self, dir: &Path, depth: usize) {
+        if depth > MAX_SCAN_DEPTH {
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with('.'))
+                .unwrap_or(true);
+            if hidden {
+                continue;
+            }
+
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                self.scan_dir(&path, depth + 1);
+            } else if file_type.is_file() && !self.contains(&path) {
+                self.insert(&path);
+            }
+        }
+    }
+
+    /// Take over entries from `other` for paths this index does not know yet.
+    pub fn merge(&mut self, other: DuplicateIndex) {
+        let known: HashSet<String> = self.files.values().flatten().map(|e| e.path.clone()).collect();
+        for (size, entries) in other.files {
+            let fresh: Vec<IndexedFile> = entries.into_iter().filter(|e| !known.contains(&e.path)).collect();
+            if !fresh.is_empty() {
+                self.files.entry(size).or_default().extend(fresh);
+            }
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+        self.files
+            .values()
+            .any(|entries| entries.iter().any(|e| e.path == path_str))
+    }
+
+    /// Find an indexed file with exactly the same content as `path`.
+    ///
+    /// Stale entries found along the way are dropped from the index. This
+    /// hashes files in place; shared indexes go through `find_duplicate_in`.
+    pub fn find_duplicate(&mut self, path: &Path) -> Option<PathBuf> {
+        let mut check = self.prepare_check(path)?;
+        let found = check.run();
+        self.finish_check(check);
+        found
+    }
+
+    /// Copy out the entries `path` has to be compared with
+    pub fn prepare_check(&self, path: &Path) -> Option<DuplicateCheck> {
+        let size = fs::metadata(path).ok()?.len();
+        if size == 0 {
+            return None;
+        }
+
+        Some(DuplicateCheck {
+            path: path.to_path_buf(),
+            size,
+            candidates: self.files.get(&size)?.clone(),
+            stale: Vec::new(),
+        })
+    }
+
+    /// Keep the hashes a check computed and drop the entries it found stale
+    pub fn finish_check(&mut self, check: DuplicateCheck) {
+        let Some(entries) = self.files.get_mut(&check.size) else {
+            return;
+        };
+
+        entries.retain(|e| {
+            !check
+                .stale
+                .iter()
+                .any(|stale| stale.path == e.path && stale.modified == e.modified)
+        });
+        for checked in check.candidates {
+            // The entry may have been refreshed meanwhile; only fill in gaps
+            if let Some(entry) = entries
+                .iter_mut()
+                .find(|e| e.path == checked.path && e.modified == checked.modified)
+            {
+                entry.fast_hash = entry.fast_hash.take().or(checked.fast_hash);
+                entry.full_hash = entry.full_hash.take().or(checked.full_hash);
+            }
+        }
+        self.files.retain(|_, entries| !entries.is_empty());
+    }
+}
+
+/// A duplicate lookup taken out of the index, so the hashing can run
+/// without holding the index lock
+pub struct DuplicateCheck {
+    path: PathBuf,
+    size: u64,
+    candidates: Vec<IndexedFile>,
+    /// Entries whose file changed or disappeared since indexing
+    stale: Vec<IndexedFile>,
+}
+
+impl DuplicateCheck {
+    /// Hash as much as needed to find a copy of the file among the candidates
+    pub fn run(&mut self) -> Option<PathBuf> {
+        let path = self.path.as_path();
+        let size = self.size;
+
+        let (current, stale): (Vec<IndexedFile>, Vec<IndexedFile>) =
+            std::mem::take(&mut self.candidates).into_iter().partition(|e| {
+                fs::metadata(&e.path)
+                    .map(|m| m.len() == e.size && modified_secs(&m) == e.modified)
+                    .unwrap_or(false)
+            });
+        self.candidates = current;
+        self.stale = stale;
+
+        let path_str = path.to_string_lossy();
+        let fast = fast_hash(path, size).ok()?;
+        let mut full: Option<String> = None;
+
+        for candidate in self.candidates.iter_mut() {
+            // Already the same file, e.g. a hard link made for an earlier duplicate
+            if candidate.path == path_str
+                || same_file::is_same_file(&candidate.path, path).unwrap_or(false)
+            {
+                continue;
+            }
+
+            if candidate.fast_hash.is_none() {
+                candidate.fast_hash = fast_hash(Path::new(&candidate.path), size).ok();
+            }
+            if candidate.fast_hash.as_deref() != Some(fast.as_str()) {
+                continue;
+            }
+
+            // Fast hashes match, confirm with the full content
+            if full.is_none() {
+                full = Some(full_hash(path).ok()?);
+            }
+            if candidate.full_hash.is_none() {
+                candidate.full_hash = full_hash(Path::new(&candidate.path)).ok();
+            }
+            if candidate.full_hash == full {
+                return Some(PathBuf::from(&candidate.path));
+            }
+        }
+
+        None
+    }
+}
+
+/// Look `path` up in a shared index, hashing on a blocking thread
+///
+/// The lock is only held to copy the candidates out and to store the hashes
+/// afterwards, so other users of the index are not held up by large files.
+pub async fn find_duplicate_in(index: &Mutex<DuplicateIndex>, path: &Path) -> Option<PathBuf> {
+    let mut check = index.lock().ok()?.prepare_check(path)?;
+    let (check, found) = tokio::task::spawn_blocking(move || {
+        let found = check.run();
+        (check, found)
+    })
+    .await
+    .ok()?;
+
+    if let Ok(mut index) = index.lock() {
+        index.finish_check(check);
+    }
+    found
+}
+
+/// Apply `action` to `newcomer`, which duplicates `existing`.
+///
+/// Returns the path the newcomer ended up at, if it still exists.
+pub fn handle_duplicate(
+    newcomer: &Path,
+    existing: &Path,
+    action: DuplicateAction,
+) -> Result<Option<PathBuf>, String> {
+    match action {
+        DuplicateAction::Skip => Ok(Some(newcomer.to_path_buf())),
+        DuplicateAction::MoveToDuplicates => {
+            let parent = newcomer.parent().ok_or("File has no parent folder")?;
+            let dest_dir = parent.join("Duplicates");
+            fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+
+            let dest = unique_path(&dest_dir, newcomer);
+            fs::rename(newcomer, &dest).map_err(|e| e.to_string())?;
+            Ok(Some(dest))
+        }
+        DuplicateAction::HardLink => {
+            // Link under a temporary name first so the newcomer is never lost
+            let tmp = link_beside(existing, newcomer)
+                .map_err(|e| format!("Hard link failed: {}", e))?;
+            if let Err(e) = fs::rename(&tmp, newcomer) {
+                let _ = fs::remove_file(&tmp);
+                return Err(e.to_string());
+            }
+            Ok(Some(newcomer.to_path_buf()))
+        }
+        DuplicateAction::Trash => {
+            trash::delete(newcomer).map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+    }
+}
+
+/// Hard-link `existing` next to `file` under a fresh hidden name.
+///
+/// `hard_link` refuses to replace an existing path, so a name that is already
+/// taken (say, by another agent linking the same file) just moves on to the
+/// next counter value.
+fn link_beside(existing: &Path, file: &Path) -> std::io::Result<PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp = file.with_file_name(format!(
+            ".{}.{}-{}.filesorter-link",
+            file_name,
+            std::process::id(),
+            n
+        ));
+        match fs::hard_link(existing, &tmp) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            result => return result.map(|_| tmp),
+        }
+    }
+}
+
+/// Pick a free name for `file` inside `dir`, adding " (N)" as needed.
+fn unique_path(dir: &Path, file: &Path) -> PathBuf {
+    let filename = file.file_name().unwrap_or_default();
+    let mut candidate = dir.join(filename);
+
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let ext = file.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let mut counter = 1;
+
+    while candidate.exists() {
+        let new_name = if ext.is_empty() {
+            format!("{} ({})", stem, counter)
+        } else {
+            format!("{} ({}).{}", stem, counter, ext)
+        };
+        candidate = dir.join(new_name);
+        counter += 1;
+    }
+
+    candidate
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Hash the size plus the first and last `SAMPLE_BYTES` of the file.
+fn fast_hash(path: &Path, size: u64) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&size.to_le_bytes());
+
+    let mut buffer = Vec::with_capacity(SAMPLE_BYTES as usize);
+    file.by_ref().take(SAMPLE_BYTES).read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+
+    if size > SAMPLE_BYTES * 2 {
+        buffer.clear();
+        file.seek(SeekFrom::End(-(SAMPLE_BYTES as i64)))?;
+        file.read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn full_hash(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("filesorter-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn finds_copies_but_not_links_to_the_same_file() {
+        let dir = temp_dir("duplicates");
+        let original = dir.join("original.txt");
+        fs::write(&original, "same content").unwrap();
+        fs::write(dir.join("other.txt"), "other content").unwrap();
+
+        let mut index = DuplicateIndex::default();
+        index.scan_folder(&dir);
+
+        let copy = dir.join("copy.txt");
+        fs::write(&copy, "same content").unwrap();
+        assert_eq!(index.find_duplicate(&copy), Some(original.clone()));
+
+        let link = dir.join("link.txt");
+        fs::hard_link(&original, &link).unwrap();
+        assert_eq!(index.find_duplicate(&link), None);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn hard_link_replaces_the_newcomer() {
+        let dir = temp_dir("hardlink");
+        let existing = dir.join("a.jpg");
+        let newcomer = dir.join("b.jpg");
+        fs::write(&existing, "picture").unwrap();
+        fs::write(&newcomer, "picture").unwrap();
+
+        let kept = handle_duplicate(&newcomer, &existing, DuplicateAction::HardLink).unwrap();
+
+        assert_eq!(kept, Some(newcomer.clone()));
+        assert!(same_file::is_same_file(&existing, &newcomer).unwrap());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn link_names_keep_the_full_file_name() {
+        let dir = temp_dir("linkname");
+        let existing = dir.join("a.jpg");
+        let newcomer = dir.join("b.jpg");
+        let bystander = dir.join("b.png");
+        fs::write(&existing, "picture").unwrap();
+
+        let first = link_beside(&existing, &newcomer).unwrap();
+        let second = link_beside(&existing, &bystander).unwrap();
+
+        assert_ne!(first, second);
+        let name = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with(".b.jpg.") && name.ends_with(".filesorter-link"));
+        assert!(same_file::is_same_file(&existing, &second).unwrap());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn saving_keeps_entries_written_by_another_agent() {
+        let dir = temp_dir("save");
+        let index_path = dir.join("hash_index.json");
+        let ours = dir.join("ours.txt");
+        let theirs = dir.join("theirs.txt");
+        let moved = dir.join("moved.txt");
+        fs::write(&ours, "ours").unwrap();
+        fs::write(&theirs, "theirs").unwrap();
+        fs::write(&moved, "moved").unwrap();
+
+        let mut other = DuplicateIndex::default();
+        other.insert(&theirs);
+        other.insert(&moved);
+        other.save_to(&index_path).unwrap();
+        fs::remove_file(&moved).unwrap();
+
+        let mut index = DuplicateIndex::default();
+        index.insert(&ours);
+        index.save_to(&index_path).unwrap();
+
+        let saved = DuplicateIndex::read(&index_path).unwrap();
+        assert!(saved.contains(&ours) && saved.contains(&theirs));
+        assert!(!saved.contains(&moved));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn checks_run_outside_the_index_and_report_back() {
+        let dir = temp_dir("check");
+        let original = dir.join("original.txt");
+        let gone = dir.join("gone.txt");
+        fs::write(&original, "same content").unwrap();
+        fs::write(&gone, "same-content").unwrap();
+
+        let mut index = DuplicateIndex::default();
+        index.scan_folder(&dir);
+        fs::remove_file(&gone).unwrap();
+
+        let copy = dir.join("copy.txt");
+        fs::write(&copy, "same content").unwrap();
+        let mut check = index.prepare_check(&copy).unwrap();
+        assert_eq!(check.run(), Some(original.clone()));
+        index.finish_check(check);
+
+        let entries: Vec<&IndexedFile> = index.files().collect();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].fast_hash.is_some() && entries[0].full_hash.is_some());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
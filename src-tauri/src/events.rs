@@ -0,0 +1,128 @@
+//! Events pushed to the UI with `AppHandle::emit_all`.
+//!
+//! Each event carries one of the payload structs below, serialized as a JSON
+//! object with the field names as written. Paths are absolute, optional
+//! fields are `null` when unknown, and times are RFC 3339 strings.
+//!
+//! | Event                   | Payload                 |
+//! |-------------------------|-------------------------|
+//! | `file-detected`         | [`FileDetected`]        |
+//! | `file-sorted`           | [`FileSorted`]          |
+//! | `file-skipped`          | [`FileSkipped`]         |
+//! | `file-failed`           | [`FileFailed`]          |
+//! | `queue-changed`         | [`QueueChanged`]        |
+//! | `connectivity-changed`  | [`ConnectivityChanged`] |
+//! | `watcher-state-changed` | [`WatcherStateChanged`] |
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+
+pub const FILE_DETECTED: &str = "file-detected";
+pub const FILE_SORTED: &str = "file-sorted";
+pub const FILE_SKIPPED: &str = "file-skipped";
+pub const FILE_FAILED: &str = "file-failed";
+pub const QUEUE_CHANGED: &str = "queue-changed";
+pub const CONNECTIVITY_CHANGED: &str = "connectivity-changed";
+pub const WATCHER_STATE_CHANGED: &str = "watcher-state-changed";
+
+/// A new file showed up in a watched folder and is about to be handled
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDetected {
+    pub path: String,
+    /// Held in the paused queue instead of being sorted now
+    pub queued: bool,
+}
+
+/// A file was moved into its category folder
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSorted {
+    pub source: String,
+    pub destination: String,
+    pub category: String,
+    pub rule_id: Option<String>,
+    pub rule_name: Option<String>,
+    /// Classifier confidence, 0.0 to 1.0
+    pub confidence: f64,
+    /// How the file was classified, e.g. `rule` or `ai`
+    pub method: String,
+}
+
+/// A file was left where it was on purpose
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSkipped {
+    pub path: String,
+    pub reason: String,
+    /// The already sorted copy, when the file was skipped as a duplicate
+    pub duplicate_of: Option<String>,
+    /// Tags the log lines about this file, see `get_logs`
+    pub file_id: Option<String>,
+}
+
+/// Handling a file ran into an error
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFailed {
+    pub path: String,
+    pub error: String,
+    /// Tags the log lines about this file, see `get_logs`
+    pub file_id: Option<String>,
+}
+
+/// Files were added to or taken from the paused queue
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueChanged {
+    /// Files now waiting in the queue
+    pub count: usize,
+}
+
+/// The API server became reachable or unreachable
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityChanged {
+    pub online: bool,
+}
+
+/// The watcher started, stopped, paused, resumed or changed folders
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherStateChanged {
+    pub running: bool,
+    pub paused: bool,
+    /// End of a timed pause
+    pub paused_until: Option<String>,
+    pub folders: Vec<String>,
+}
+
+pub fn emit<P: Serialize + Clone>(app: &AppHandle, event: &str, payload: P) {
+    if let Err(e) = app.emit_all(event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+pub fn queue_changed(app: &AppHandle) {
+    let count = app
+        .state::<AppState>()
+        .storage
+        .lock()
+        .map(|storage| storage.paused_queue().len())
+        .unwrap_or(0);
+    emit(app, QUEUE_CHANGED, QueueChanged { count });
+}
+
+pub fn watcher_state_changed(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let payload = WatcherStateChanged {
+        running: state.watcher.lock().map(|w| w.is_some()).unwrap_or(false),
+        paused: state.is_paused.lock().map(|p| *p).unwrap_or(false),
+        paused_until: state
+            .paused_until
+            .lock()
+            .ok()
+            .and_then(|until| until.map(|t| t.to_rfc3339())),
+        folders: state
+            .config
+            .lock()
+            .map(|c| c.enabled_folders())
+            .unwrap_or_default(),
+    };
+    emit(app, WATCHER_STATE_CHANGED, payload);
+}
@@ -0,0 +1,231 @@
+//! Exporting the move history and a summary report for auditing.
+//!
+//! Both come as CSV with a header row, or as JSON Lines with one object per
+//! row. Timestamps are RFC 3339 in local time. The history includes undone
+//! moves, flagged in the `undone` column; the summary counts only moves that
+//! are still in effect. CSV text that would read as a spreadsheet formula
+//! is prefixed with `'`.
+
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::statistics::{self, Bucket, StatisticsQuery};
+use crate::storage::{LocalStorage, MoveOrder, MoveQuery, MoveRecord};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// Every move in the range
+    History,
+    /// Totals per category, rule, method, folder and day
+    Summary,
+}
+
+const HISTORY_COLUMNS: &[&str] = &[
+    "id",
+    "timestamp",
+    "filename",
+    "source_path",
+    "dest_path",
+    "category",
+    "rule_id",
+    "rule_name",
+    "confidence",
+    "method",
+    "size_bytes",
+    "undone",
+];
+
+const SUMMARY_COLUMNS: &[&str] = &["section", "key", "label", "files", "bytes", "average_confidence"];
+
+fn history_row(record: &MoveRecord) -> Value {
+    let timestamp = DateTime::from_timestamp(record.timestamp, 0)
+        .map(|t| t.with_timezone(&Local).to_rfc3339())
+        .unwrap_or_default();
+
+    json!({
+        "id": record.id,
+        "timestamp": timestamp,
+        "filename": record.filename,
+        "source_path": record.source_path,
+        "dest_path": record.dest_path,
+        "category": record.category,
+        "rule_id": record.rule_id,
+        "rule_name": record.rule_name,
+        "confidence": record.confidence,
+        "method": record.method,
+        "size_bytes": record.size_bytes,
+        "undone": record.undone,
+    })
+}
+
+fn summary_row(section: &str, bucket: &Bucket) -> Value {
+    json!({
+        "section": section,
+        "key": bucket.key,
+        "label": bucket.label,
+        "files": bucket.files,
+        "bytes": bucket.bytes,
+        "average_confidence": bucket.average_confidence,
+    })
+}
+
+/// Characters that make spreadsheets read a cell as a formula
+const FORMULA_PREFIXES: &[char] = &['=', '+', '-', '@', '\t', '\r'];
+
+/// Quote a CSV field when it contains a separator, quote or line break
+///
+/// Text that a spreadsheet would run as a formula, such as a file named
+/// `=HYPERLINK(...)`, is prefixed with `'` so it stays text.
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) if text.starts_with(FORMULA_PREFIXES) => format!("'{}", text),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn write_rows(
+    out: &mut impl Write,
+    format: ExportFormat,
+    columns: &[&str],
+    rows: &[Value],
+) -> std::io::Result<()> {
+    match format {
+        ExportFormat::Csv => {
+            writeln!(out, "{}", columns.join(","))?;
+            for row in rows {
+                let fields: Vec<String> = columns.iter().map(|c| csv_field(&row[*c])).collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+        }
+        ExportFormat::Jsonl => {
+            for row in rows {
+                writeln!(out, "{}", row)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn history_rows(storage: &LocalStorage, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Vec<Value> {
+    let query = MoveQuery {
+        from: from.and_then(statistics::local_midnight),
+        to: to.and_then(|day| statistics::local_midnight(day + Duration::days(1))),
+        include_undone: true,
+        order: MoveOrder::Oldest,
+        limit: u32::MAX,
+        ..Default::default()
+    };
+    let (records, _) = storage.search_moves(&query);
+    records.iter().map(history_row).collect()
+}
+
+fn summary_rows(
+    storage: &LocalStorage,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    watched: &[String],
+) -> Vec<Value> {
+    let stats = statistics::compute(storage, &StatisticsQuery { from, to }, watched);
+
+    let mut rows = vec![summary_row("total", &stats.total)];
+    for (method, files) in &stats.methods {
+        rows.push(json!({ "section": "method", "key": method, "files": files }));
+    }
+
+    let sections = [
+        ("category", &stats.by_category),
+        ("rule", &stats.by_rule),
+        ("folder", &stats.by_folder),
+        ("day", &stats.by_day),
+        ("week", &stats.by_week),
+    ];
+    for (section, buckets) in sections {
+        rows.extend(buckets.iter().map(|bucket| summary_row(section, bucket)));
+    }
+    rows
+}
+
+/// Rows of an export, read from storage and ready to be written
+pub struct Export {
+    kind: ExportKind,
+    columns: &'static [&'static str],
+    rows: Vec<Value>,
+}
+
+impl Export {
+    /// Read the rows of an export; the file is written separately by `write`
+    pub fn collect(
+        storage: &LocalStorage,
+        kind: ExportKind,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        watched: &[String],
+    ) -> Self {
+        let (columns, rows) = match kind {
+            ExportKind::History => (HISTORY_COLUMNS, history_rows(storage, from, to)),
+            ExportKind::Summary => (SUMMARY_COLUMNS, summary_rows(storage, from, to, watched)),
+        };
+        Self { kind, columns, rows }
+    }
+
+    /// Write the rows to `path` and return how many were written
+    pub fn write(&self, format: ExportFormat, path: &Path) -> Result<usize, String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        write_rows(&mut out, format, self.columns, &self.rows)
+            .and_then(|_| out.flush())
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+
+        log::info!("Exported {} {:?} rows to {}", self.rows.len(), self.kind, path.display());
+        Ok(self.rows.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field(&json!("plain.pdf")), "plain.pdf");
+        assert_eq!(csv_field(&json!("a,b.pdf")), "\"a,b.pdf\"");
+        assert_eq!(csv_field(&json!("say \"hi\".txt")), "\"say \"\"hi\"\".txt\"");
+        assert_eq!(csv_field(&json!("two\nlines")), "\"two\nlines\"");
+        assert_eq!(csv_field(&Value::Null), "");
+        assert_eq!(csv_field(&json!(0.5)), "0.5");
+        assert_eq!(csv_field(&json!(false)), "false");
+    }
+
+    #[test]
+    fn csv_fields_never_start_a_formula() {
+        assert_eq!(csv_field(&json!("=HYPERLINK(\"x\")")), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field(&json!("+1.pdf")), "'+1.pdf");
+        assert_eq!(csv_field(&json!("-rf")), "'-rf");
+        assert_eq!(csv_field(&json!("@sum")), "'@sum");
+        assert_eq!(csv_field(&json!("\tx")), "'\tx");
+        assert_eq!(csv_field(&json!("\rx")), "\"'\rx\"");
+        assert_eq!(csv_field(&json!("a=b")), "a=b");
+        // Numbers keep their sign
+        assert_eq!(csv_field(&json!(-1)), "-1");
+    }
+}
@@ -1,157 +1,466 @@
 //! File watching and processing module.
 
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
 use tauri::AppHandle;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 
-use crate::api_client::{self, ActionLogRequest, ClassifyRequest};
+use crate::api_client::{self, ActionLogRequest, ClassifyRequest, ClassifyResponse};
+use crate::config::AppConfig;
+use crate::duplicates::{self, DuplicateAction, DuplicateIndex};
+use crate::events;
+use crate::logging;
+use crate::path_guard::{self, PathPolicy};
+use crate::storage::{DailyCounter, LocalStorage, MoveRecord, PendingAction};
+
+/// Settings the watcher reads for every file, so edits apply without a restart
+#[derive(Debug, Clone)]
+pub struct WatcherSettings {
+    pub folders: Vec<String>,
+    pub api_url: String,
+    pub token: String,
+    pub processing_delay: Duration,
+    pub duplicate_action: DuplicateAction,
+    /// Files queued during a pause wait for the user instead of being sorted
+    pub review_paused_files: bool,
+    /// Every move target must pass this, whatever the server suggests
+    pub policy: PathPolicy,
+}
+
+impl WatcherSettings {
+    /// Build settings from the config, or `None` when not logged in
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        Some(Self {
+            folders: config.enabled_folders(),
+            api_url: config.api_url.clone(),
+            token: config.access_token.clone()?,
+            processing_delay: Duration::from_secs(config.processing_delay_seconds),
+            duplicate_action: config.duplicate_action,
+            review_paused_files: config.review_paused_files,
+            policy: PathPolicy::from_config(config),
+        })
+    }
+}
 
 pub struct FileWatcher {
-    folders: Vec<String>,
-    api_url: String,
-    token: String,
-    app_handle: AppHandle,
+    settings: Arc<Mutex<WatcherSettings>>,
+    /// The GUI app, or `None` when running headless
+    app_handle: Option<AppHandle>,
     is_paused: Arc<Mutex<bool>>,
-    files_today: Arc<Mutex<u32>>,
-    processed_files: Arc<Mutex<HashSet<PathBuf>>>,
+    storage: Arc<Mutex<LocalStorage>>,
+    duplicate_index: Arc<Mutex<DuplicateIndex>>,
+    /// Sends folder lists to the notify thread; dropping it stops the thread
+    folder_updates: Mutex<Option<std::sync::mpsc::Sender<Vec<String>>>>,
+    /// Feeds files into the processing loop, used to release the paused queue
+    events: Mutex<Option<mpsc::Sender<PathBuf>>>,
 }
 
 impl FileWatcher {
     pub fn new(
-        folders: Vec<String>,
-        api_url: String,
-        token: String,
-        app_handle: AppHandle,
+        settings: WatcherSettings,
+        app_handle: Option<AppHandle>,
         is_paused: Arc<Mutex<bool>>,
-        files_today: Arc<Mutex<u32>>,
+        storage: Arc<Mutex<LocalStorage>>,
+        duplicate_index: Arc<Mutex<DuplicateIndex>>,
     ) -> Self {
         Self {
-            folders,
-            api_url,
-            token,
+            settings: Arc::new(Mutex::new(settings)),
             app_handle,
             is_paused,
-            files_today,
-            processed_files: Arc::new(Mutex::new(HashSet::new())),
+            storage,
+            duplicate_index,
+            folder_updates: Mutex::new(None),
+            events: Mutex::new(None),
         }
     }
 
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let (tx, mut rx) = mpsc::channel::<PathBuf>(100);
+        let (folders_tx, folders_rx) = std::sync::mpsc::channel::<Vec<String>>();
 
-        let folders = self.folders.clone();
-        
-        // Spawn the watcher in a blocking thread
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            let tx_clone = tx.clone();
-            
-            let mut watcher = RecommendedWatcher::new(
-                move |res: Result<Event, notify::Error>| {
-                    if let Ok(event) = res {
-                        for path in event.paths {
-                            if path.is_file() {
-                                let tx = tx_clone.clone();
-                                rt.spawn(async move {
-                                    let _ = tx.send(path).await;
-                                });
-                            }
+        let folders = self.settings.lock().unwrap().folders.clone();
+        *self.events.lock().unwrap() = Some(tx.clone());
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| {
+                if let Ok(event) = res {
+                    for path in event.paths {
+                        if path.is_file() {
+                            let _ = tx.blocking_send(path);
                         }
                     }
-                },
-                Config::default(),
-            ).unwrap();
-
-            for folder in &folders {
-                let path = PathBuf::from(folder);
-                if path.exists() {
-                    watcher.watch(&path, RecursiveMode::NonRecursive).ok();
-                    log::info!("Watching folder: {}", folder);
                 }
-            }
+            },
+            Config::default(),
+        )?;
+
+        // Own the notify watcher in a plain thread and apply folder changes to it
+        std::thread::spawn(move || {
+            let mut watched = HashSet::new();
+            sync_watches(&mut watcher, &mut watched, &folders);
 
-            // Keep the watcher alive
-            loop {
-                std::thread::sleep(Duration::from_secs(1));
+            // Ends once the FileWatcher, and with it the sender, is dropped
+            while let Ok(folders) = folders_rx.recv() {
+                sync_watches(&mut watcher, &mut watched, &folders);
             }
+
+            log::info!("File watcher stopped");
         });
 
+        *self.folder_updates.lock().unwrap() = Some(folders_tx);
+
         // Process events
-        let api_url = self.api_url.clone();
-        let token = self.token.clone();
-        let app_handle = self.app_handle.clone();
+        let settings = self.settings.clone();
         let is_paused = self.is_paused.clone();
-        let files_today = self.files_today.clone();
-        let processed_files = self.processed_files.clone();
+        let ctx = ProcessContext {
+            app_handle: self.app_handle.clone(),
+            storage: self.storage.clone(),
+            duplicate_index: self.duplicate_index.clone(),
+        };
 
         tokio::spawn(async move {
+            // Index what is already in the watched folders and their sorted subfolders
+            let folders = settings.lock().unwrap().folders.clone();
+            let duplicate_index = ctx.duplicate_index.clone();
+            tokio::task::spawn_blocking(move || index_folders(&duplicate_index, &folders))
+                .await
+                .ok();
+
+            // Catch up on actions the last run could not report
+            let (api_url, token) = {
+                let settings = settings.lock().unwrap();
+                (settings.api_url.clone(), settings.token.clone())
+            };
+            tokio::spawn(send_pending_actions(api_url, token, ctx.storage.clone()));
+
             while let Some(path) = rx.recv().await {
-                // Skip if paused
-                if *is_paused.lock().unwrap() {
+                // Skip temp files
+                let filename = path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("");
+                
+                if filename.starts_with('.') || filename.starts_with('~') {
                     continue;
                 }
 
-                // Skip if already processed
+                let path_str = path.to_string_lossy().to_string();
+
+                // Hold the file until the watcher resumes
+                if *is_paused.lock().unwrap() {
+                    if ctx.storage.lock().unwrap().queue_paused(&path_str) {
+                        ctx.detected(&path_str, true);
+                        ctx.queue_changed();
+                    }
+                    continue;
+                }
+                // A queued file that changes again keeps waiting for review;
+                // files the user releases are taken off the queue first
+                let review = settings.lock().unwrap().review_paused_files;
                 {
-                    let processed = processed_files.lock().unwrap();
-                    if processed.contains(&path) {
-                        continue;
+                    let mut storage = ctx.storage.lock().unwrap();
+                    if storage.is_queued(&path_str) {
+                        if review {
+                            continue;
+                        }
+                        storage.unqueue_paused(&path_str);
+                        drop(storage);
+                        ctx.queue_changed();
                     }
                 }
 
+                // In-flight files keep the settings they started with
+                let current = settings.lock().unwrap().clone();
+
                 // Wait for file to be fully written
-                sleep(Duration::from_secs(3)).await;
+                sleep(current.processing_delay).await;
 
                 // Skip if file no longer exists
-                if !path.exists() {
+                let Some((size, modified)) = file_identity(&path) else {
                     continue;
-                }
+                };
 
-                // Skip temp files
-                let filename = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("");
-                
-                if filename.starts_with('.') || filename.starts_with('~') {
+                // Skip if this exact file was already processed
+                if ctx.storage.lock().unwrap().is_processed(&path_str, size, modified) {
                     continue;
                 }
 
-                // Process the file
-                if let Err(e) = process_file(
-                    &path,
-                    &api_url,
-                    &token,
-                    &app_handle,
-                    &files_today,
-                ).await {
-                    log::error!("Error processing file {:?}: {}", path, e);
+                ctx.detected(&path_str, false);
+
+                // Process the file, tagging its log lines with one id
+                let outcome = logging::with_file_id(async {
+                    let outcome = process_file(&path, &current, &ctx).await.unwrap_or_else(|e| {
+                        log::error!("Error processing file {:?}: {}", path, e);
+                        FileOutcome::Failed { error: e.to_string() }
+                    });
+                    ctx.count(&outcome);
+                    ctx.report(&path, &outcome);
+                    outcome
+                })
+                .await;
+
+                // Mark as processed; failures are retried on the next event or start
+                if outcome.is_settled() {
+                    ctx.storage.lock().unwrap().mark_processed(&path_str, size, modified);
                 }
+            }
+        });
 
-                // Mark as processed
-                {
-                    let mut processed = processed_files.lock().unwrap();
-                    processed.insert(path);
+        Ok(())
+    }
+
+    /// Send files through the processing loop as if they had just appeared
+    ///
+    /// Files outside the folders watched now are skipped, so a folder removed
+    /// during a pause is not sorted after all. Returns how many were sent.
+    pub fn requeue(&self, paths: Vec<PathBuf>) -> Result<usize, String> {
+        let events = self
+            .events
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or("Watcher is not running")?;
+
+        let folders = self.settings.lock().unwrap().folders.clone();
+        let (paths, stale): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|path| {
+            path.parent()
+                .is_some_and(|parent| folders.iter().any(|folder| Path::new(folder) == parent))
+        });
+        for path in &stale {
+            log::info!("Dropping queued {:?}: its folder is no longer watched", path);
+        }
+        let count = paths.len();
+
+        // The channel is bounded, so feed it from a task instead of blocking
+        tauri::async_runtime::spawn(async move {
+            for path in paths {
+                if events.send(path).await.is_err() {
+                    break;
                 }
             }
         });
+        Ok(count)
+    }
 
-        Ok(())
+    /// Apply a changed config to the running watcher
+    ///
+    /// Folder watches are added and removed incrementally; files already
+    /// waiting to be processed are not affected.
+    pub fn apply_config(&self, config: &AppConfig) {
+        let Some(new_settings) = WatcherSettings::from_config(config) else {
+            return;
+        };
+
+        let added: Vec<String> = {
+            let mut settings = self.settings.lock().unwrap();
+            let added = new_settings
+                .folders
+                .iter()
+                .filter(|f| !settings.folders.contains(f))
+                .cloned()
+                .collect();
+            *settings = new_settings.clone();
+            added
+        };
+
+        if let Some(updates) = self.folder_updates.lock().unwrap().as_ref() {
+            let _ = updates.send(new_settings.folders);
+        }
+
+        if !added.is_empty() {
+            let duplicate_index = self.duplicate_index.clone();
+            tauri::async_runtime::spawn_blocking(move || index_folders(&duplicate_index, &added));
+        }
     }
 }
 
-async fn process_file(
-    path: &PathBuf,
-    api_url: &str,
-    token: &str,
-    app_handle: &AppHandle,
-    files_today: &Arc<Mutex<u32>>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Walk `folders` into a separate index, then merge it in under a short lock
+fn index_folders(duplicate_index: &Mutex<DuplicateIndex>, folders: &[String]) {
+    let mut scanned = DuplicateIndex::default();
+    for folder in folders {
+        scanned.scan_folder(&PathBuf::from(folder));
+    }
+
+    let mut index = duplicate_index.lock().unwrap();
+    index.merge(scanned);
+    index.save().ok();
+}
+
+/// Bring the set of watched folders in line with `folders`
+fn sync_watches(watcher: &mut RecommendedWatcher, watched: &mut HashSet<PathBuf>, folders: &[String]) {
+    let wanted: HashSet<PathBuf> = folders.iter().map(PathBuf::from).collect();
+
+    let removed: Vec<PathBuf> = watched.difference(&wanted).cloned().collect();
+    for path in removed {
+        watcher.unwatch(&path).ok();
+        watched.remove(&path);
+        log::info!("Stopped watching folder: {}", path.display());
+    }
+
+    let added: Vec<PathBuf> = wanted.difference(watched).cloned().collect();
+    for path in added {
+        if path.exists() && watcher.watch(&path, RecursiveMode::NonRecursive).is_ok() {
+            log::info!("Watching folder: {}", path.display());
+            watched.insert(path);
+        } else {
+            log::warn!("Cannot watch folder: {}", path.display());
+        }
+    }
+}
+
+/// What happened to a single file
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum FileOutcome {
+    Sorted {
+        dest_path: String,
+        category: String,
+        rule_id: Option<String>,
+        rule_name: Option<String>,
+        confidence: f64,
+        method: String,
+    },
+    Duplicate { existing: String },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+impl FileOutcome {
+    /// Whether the file is done with; failed files get another try later
+    pub fn is_settled(&self) -> bool {
+        !matches!(self, FileOutcome::Failed { .. })
+    }
+}
+
+/// Shared state `process_file` needs besides the current settings
+#[derive(Clone)]
+pub struct ProcessContext {
+    /// The GUI app, or `None` when running headless
+    pub app_handle: Option<AppHandle>,
+    pub storage: Arc<Mutex<LocalStorage>>,
+    pub duplicate_index: Arc<Mutex<DuplicateIndex>>,
+}
+
+impl ProcessContext {
+    fn notify(&self, title: &str, body: String) {
+        if let Some(app_handle) = &self.app_handle {
+            let _ = tauri::api::notification::Notification::new(&app_handle.config().tauri.bundle.identifier)
+                .title(title)
+                .body(body)
+                .show();
+        }
+    }
+
+    /// Add the outcome to today's counters
+    ///
+    /// Sorted files are counted when their move is journaled.
+    fn count(&self, outcome: &FileOutcome) {
+        let counter = match outcome {
+            FileOutcome::Sorted { .. } => {
+                if let Some(app_handle) = &self.app_handle {
+                    crate::refresh_tray_menu(app_handle);
+                }
+                return;
+            }
+            FileOutcome::Duplicate { .. } => DailyCounter::Duplicates,
+            FileOutcome::Skipped { .. } => DailyCounter::Skipped,
+            FileOutcome::Failed { .. } => DailyCounter::Failed,
+        };
+        self.storage
+            .lock()
+            .unwrap()
+            .count_daily(chrono::Local::now().date_naive(), counter);
+    }
+
+    fn detected(&self, path: &str, queued: bool) {
+        if let Some(app_handle) = &self.app_handle {
+            let payload = events::FileDetected {
+                path: path.to_string(),
+                queued,
+            };
+            events::emit(app_handle, events::FILE_DETECTED, payload);
+        }
+    }
+
+    fn queue_changed(&self) {
+        if let Some(app_handle) = &self.app_handle {
+            events::queue_changed(app_handle);
+        }
+    }
+
+    /// Tell the UI what happened, or print a JSON line when running headless
+    fn report(&self, path: &Path, outcome: &FileOutcome) {
+        let Some(app_handle) = &self.app_handle else {
+            let mut line = serde_json::to_value(outcome).unwrap_or_default();
+            line["event"] = serde_json::Value::from("file");
+            line["path"] = serde_json::Value::from(path.to_string_lossy());
+            line["file_id"] = serde_json::Value::from(logging::current_file_id());
+            println!("{}", line);
+            return;
+        };
+
+        let path = path.to_string_lossy().to_string();
+        match outcome.clone() {
+            FileOutcome::Sorted {
+                dest_path,
+                category,
+                rule_id,
+                rule_name,
+                confidence,
+                method,
+            } => {
+                let payload = events::FileSorted {
+                    source: path,
+                    destination: dest_path,
+                    category,
+                    rule_id,
+                    rule_name,
+                    confidence,
+                    method,
+                };
+                events::emit(app_handle, events::FILE_SORTED, payload);
+            }
+            FileOutcome::Duplicate { existing } => {
+                let payload = events::FileSkipped {
+                    path,
+                    reason: "duplicate".to_string(),
+                    duplicate_of: Some(existing),
+                    file_id: logging::current_file_id(),
+                };
+                events::emit(app_handle, events::FILE_SKIPPED, payload);
+            }
+            FileOutcome::Skipped { reason } => {
+                let payload = events::FileSkipped {
+                    path,
+                    reason,
+                    duplicate_of: None,
+                    file_id: logging::current_file_id(),
+                };
+                events::emit(app_handle, events::FILE_SKIPPED, payload);
+            }
+            FileOutcome::Failed { error } => {
+                let payload = events::FileFailed {
+                    path,
+                    error,
+                    file_id: logging::current_file_id(),
+                };
+                events::emit(app_handle, events::FILE_FAILED, payload);
+            }
+        }
+    }
+}
+
+/// Classify a file and work out the folder it belongs in, without moving it
+pub async fn plan_file(
+    path: &Path,
+    settings: &WatcherSettings,
+) -> Result<(ClassifyResponse, PathBuf), Box<dyn std::error::Error + Send + Sync>> {
     let filename = path.file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown")
@@ -171,17 +480,71 @@ async fn process_file(
         None
     };
 
-    log::info!("Processing file: {}", filename);
-
-    // Classify the file
     let classify_request = ClassifyRequest {
-        filename: filename.clone(),
-        extension: extension.clone(),
+        filename,
+        extension,
         size_bytes: size,
         content_preview,
     };
 
-    let classification = api_client::classify_file(api_url, token, &classify_request).await?;
+    let classification = api_client::classify_file(&settings.api_url, &settings.token, &classify_request).await?;
+
+    // Build destination path, refusing anything outside the watched folder
+    let source_dir = path.parent().ok_or("file has no parent folder")?;
+    let dest_dir = path_guard::resolve_destination(source_dir, &classification.destination)?;
+    settings.policy.check_destination(&dest_dir)?;
+
+    Ok((classification, dest_dir))
+}
+
+pub async fn process_file(
+    path: &Path,
+    settings: &WatcherSettings,
+    ctx: &ProcessContext,
+) -> Result<FileOutcome, Box<dyn std::error::Error + Send + Sync>> {
+    let filename = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    
+    let size = path.metadata().map(|m| m.len()).ok();
+
+    // Flag exact duplicates before spending a classification call on them
+    let duplicate = duplicates::find_duplicate_in(&ctx.duplicate_index, path).await;
+    if let Some(existing) = duplicate {
+        log::info!(
+            "{} duplicates {:?}, applying {:?}",
+            filename,
+            existing,
+            settings.duplicate_action
+        );
+        let kept = duplicates::handle_duplicate(path, &existing, settings.duplicate_action)?;
+
+        // A hard link replaces the file and changes its mtime; record what is
+        // there now so the watcher event it causes is recognised as handled
+        if let Some(kept) = kept {
+            if let Some((size, modified)) = file_identity(&kept) {
+                ctx.storage
+                    .lock()
+                    .unwrap()
+                    .mark_processed(&kept.to_string_lossy(), size, modified);
+            }
+        }
+
+        ctx.notify(
+            "Найден дубликат",
+            format!("{} уже есть в {}", filename, existing.display()),
+        );
+
+        return Ok(FileOutcome::Duplicate {
+            existing: existing.to_string_lossy().to_string(),
+        });
+    }
+
+    log::info!("Processing file: {}", filename);
+
+    // Classify the file
+    let (classification, dest_dir) = plan_file(path, settings).await?;
     
     log::info!(
         "Classified {} -> {} ({})",
@@ -189,10 +552,6 @@ async fn process_file(
         classification.destination,
         classification.classification_method
     );
-
-    // Build destination path
-    let source_dir = path.parent().unwrap();
-    let dest_dir = source_dir.join(&classification.destination);
     
     // Create destination directory
     std::fs::create_dir_all(&dest_dir)?;
@@ -228,9 +587,11 @@ async fn process_file(
                     counter += 1;
                 }
             },
-            "skip" | _ => {
+            _ => {
                 log::info!("Skipping {} because it exists and strategy is skip", filename);
-                return Ok(());
+                return Ok(FileOutcome::Skipped {
+                    reason: "destination exists".to_string(),
+                });
             }
         }
     }
@@ -242,6 +603,28 @@ async fn process_file(
     
     log::info!("Moved {} to {:?}", filename, dest_path);
 
+    ctx.storage.lock().unwrap().record_move(&MoveRecord {
+        id: 0,
+        filename: filename.clone(),
+        source_path: path.to_string_lossy().to_string(),
+        dest_path: dest_path.to_string_lossy().to_string(),
+        category: Some(classification.category.clone()),
+        rule_id: classification.rule_id.clone(),
+        rule_name: classification.rule_name.clone(),
+        confidence: Some(classification.confidence),
+        method: classification.classification_method.clone(),
+        size_bytes: size.unwrap_or(0),
+        timestamp: chrono::Utc::now().timestamp(),
+        undone: false,
+    });
+
+    {
+        let mut index = ctx.duplicate_index.lock().unwrap();
+        index.remove(path);
+        index.insert(&dest_path);
+        index.save().ok();
+    }
+
     // Log the action
     let action_request = ActionLogRequest {
         filename: filename.clone(),
@@ -252,21 +635,186 @@ async fn process_file(
         confidence: classification.confidence,
     };
 
-    api_client::log_action(api_url, token, &action_request).await?;
-
-    // Update counter
-    {
-        let mut count = files_today.lock().unwrap();
-        *count += 1;
+    // The file is sorted either way; an offline server hears about it later
+    match api_client::log_action(&settings.api_url, &settings.token, &action_request).await {
+        Ok(_) => {
+            tokio::spawn(send_pending_actions(
+                settings.api_url.clone(),
+                settings.token.clone(),
+                ctx.storage.clone(),
+            ));
+        }
+        Err(e) if api_client::is_online() == Some(false) => {
+            log::warn!("Server unreachable, queued the log of {}: {}", filename, e);
+            ctx.storage.lock().unwrap().add_pending_action(PendingAction {
+                id: 0,
+                filename: action_request.filename.clone(),
+                source_path: action_request.source_path.clone(),
+                dest_path: action_request.dest_path.clone(),
+                category_id: action_request.category_id.clone(),
+                rule_id: action_request.rule_id.clone(),
+                confidence: action_request.confidence,
+                timestamp: chrono::Utc::now().timestamp(),
+            });
+        }
+        Err(e) => log::warn!("Server did not accept the log of {}: {}", filename, e),
     }
 
     // Send notification
-    let _ = tauri::api::notification::Notification::new(&app_handle.config().tauri.bundle.identifier)
-        .title("Файл отсортирован")
-        .body(&format!("{} → {}", filename, classification.destination))
-        .show();
+    ctx.notify(
+        "Файл отсортирован",
+        format!("{} → {}", filename, classification.destination),
+    );
+
+    Ok(FileOutcome::Sorted {
+        dest_path: dest_path.to_string_lossy().to_string(),
+        category: classification.category,
+        rule_id: action_request.rule_id,
+        rule_name: classification.rule_name,
+        confidence: classification.confidence,
+        method: classification.classification_method,
+    })
+}
+
+/// Set while pending actions are being sent, so two senders never overlap
+static SENDING_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Report actions queued while the server was unreachable, oldest first
+///
+/// Stops at the first network error and leaves the rest queued. An action
+/// the server answers but refuses is dropped, as retrying would not help.
+pub async fn send_pending_actions(api_url: String, token: String, storage: Arc<Mutex<LocalStorage>>) {
+    if SENDING_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let pending = storage.lock().map(|s| s.pending_actions()).unwrap_or_default();
+    let mut sent = 0;
+    for action in pending {
+        let request = ActionLogRequest {
+            filename: action.filename.clone(),
+            source_path: action.source_path,
+            dest_path: action.dest_path,
+            category_id: action.category_id,
+            rule_id: action.rule_id,
+            confidence: action.confidence,
+        };
+
+        match api_client::log_action(&api_url, &token, &request).await {
+            Ok(_) => sent += 1,
+            Err(e) if api_client::is_online() == Some(false) => {
+                log::info!("Pending actions wait for the server: {}", e);
+                break;
+            }
+            Err(e) => log::warn!("Server refused the pending log of {}, dropped: {}", action.filename, e),
+        }
+        if let Ok(mut storage) = storage.lock() {
+            storage.remove_pending_action(action.id);
+        }
+    }
+
+    if sent > 0 {
+        log::info!("Sent {} pending actions to the server", sent);
+    }
+    SENDING_PENDING.store(false, Ordering::SeqCst);
+}
+
+/// Sort every file directly inside `dir` once, or only plan it on a dry run
+pub async fn organize_folder(
+    dir: &Path,
+    dry_run: bool,
+    settings: &WatcherSettings,
+    ctx: &ProcessContext,
+) -> Result<serde_json::Value, String> {
+    if !dir.is_dir() {
+        return Err(format!("Not a folder: {}", dir.display()));
+    }
+    settings.policy.check(dir)?;
+
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            !name.starts_with('.') && !name.starts_with('~')
+        })
+        .collect();
+    paths.sort();
+
+    let mut files = Vec::new();
+    let mut sorted = 0;
+    let mut failed = 0;
+
+    for path in paths {
+        // Tag the file's log lines and its result with the same id
+        let mut entry = logging::with_file_id(async {
+            let mut entry = if dry_run {
+                match plan_file(&path, settings).await {
+                    Ok((classification, dest_dir)) => serde_json::json!({
+                        "outcome": "planned",
+                        "dest_dir": dest_dir.to_string_lossy(),
+                        "category": classification.category,
+                        "confidence": classification.confidence,
+                        "method": classification.classification_method,
+                    }),
+                    Err(e) => {
+                        failed += 1;
+                        serde_json::json!({ "outcome": "failed", "error": e.to_string() })
+                    }
+                }
+            } else {
+                let outcome = process_file(&path, settings, ctx).await.unwrap_or_else(|e| {
+                    log::error!("Error processing file {:?}: {}", path, e);
+                    FileOutcome::Failed { error: e.to_string() }
+                });
+                ctx.count(&outcome);
+
+                match &outcome {
+                    FileOutcome::Sorted { .. } => sorted += 1,
+                    FileOutcome::Failed { .. } => failed += 1,
+                    _ => {}
+                }
+                // Keep the watcher from sorting the file a second time
+                if let Some((size, modified)) = file_identity(&path).filter(|_| outcome.is_settled()) {
+                    ctx.storage.lock().unwrap().mark_processed(&path.to_string_lossy(), size, modified);
+                }
+                serde_json::to_value(&outcome).unwrap_or_default()
+            };
+            entry["file_id"] = serde_json::Value::from(logging::current_file_id());
+            entry
+        })
+        .await;
+
+        entry["path"] = serde_json::Value::from(path.to_string_lossy());
+        files.push(entry);
+    }
+
+    Ok(serde_json::json!({
+        "dir": dir.to_string_lossy(),
+        "dry_run": dry_run,
+        "sorted": sorted,
+        "failed": failed,
+        "files": files,
+    }))
+}
+
+/// Size and modification time, used to recognise a file we already handled
+pub fn file_identity(path: &Path) -> Option<(u64, i64)> {
+    let metadata = path.metadata().ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
 
-    Ok(())
+    Some((metadata.len(), modified))
 }
 
 fn is_text_file(extension: &str) -> bool {
@@ -278,7 +826,7 @@ fn is_text_file(extension: &str) -> bool {
     )
 }
 
-fn read_content_preview(path: &PathBuf) -> Result<String, std::io::Error> {
+fn read_content_preview(path: &Path) -> Result<String, std::io::Error> {
     use std::io::Read;
     
     let mut file = std::fs::File::open(path)?;
@@ -0,0 +1,34 @@
+//! Filesystem helpers shared by the persistence modules.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Write `contents` to `path` so readers see either the old or the new file.
+///
+/// Data goes to a uniquely named sibling temp file, so concurrent writers
+/// from the GUI and a headless run do not clobber each other's, is fsynced,
+/// and then renamed over the target. The parent directory is synced too where
+/// the platform allows it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4().simple()));
+
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! Headless mode: the `filesorter` subcommands, run without the GUI.
+//!
+//! Each command prints one JSON document to stdout; `watch` prints one JSON
+//! object per line as files are handled. Failures print `{"error": ...}` and
+//! exit with status 1.
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::api_client;
+use crate::classifier::LocalRule;
+use crate::cli::{Command, RulesCommand};
+use crate::config::AppConfig;
+use crate::control;
+use crate::duplicates::DuplicateIndex;
+use crate::export::{self, ExportFormat, ExportKind};
+use crate::file_watcher::{self, FileWatcher, ProcessContext, WatcherSettings};
+use crate::instance::{self, Role};
+use crate::statistics;
+use crate::storage::LocalStorage;
+use crate::undo;
+
+/// Environment variable `login` reads the password from
+const PASSWORD_ENV: &str = "FILESORTER_PASSWORD";
+
+/// State shared by the commands, loaded the same way the GUI loads it
+struct Engine {
+    config: AppConfig,
+    storage: Arc<Mutex<LocalStorage>>,
+    duplicate_index: Arc<Mutex<DuplicateIndex>>,
+}
+
+impl Engine {
+    fn load() -> Result<Self, String> {
+        let (config, recovery) = AppConfig::load().map_err(|e| e.to_string())?;
+        if let Some(recovery) = recovery {
+            log::warn!("Config was unreadable and has been replaced: {}", recovery.reason);
+        }
+
+        let storage = LocalStorage::open().unwrap_or_else(|e| {
+            log::error!("Failed to open local storage, history will not be kept: {}", e);
+            LocalStorage::in_memory()
+        });
+
+        Ok(Self {
+            config,
+            storage: Arc::new(Mutex::new(storage)),
+            duplicate_index: Arc::new(Mutex::new(DuplicateIndex::load())),
+        })
+    }
+
+    fn settings(&self) -> Result<WatcherSettings, String> {
+        WatcherSettings::from_config(&self.config).ok_or_else(|| "Not logged in".to_string())
+    }
+
+    fn context(&self) -> ProcessContext {
+        ProcessContext {
+            app_handle: None,
+            storage: self.storage.clone(),
+            duplicate_index: self.duplicate_index.clone(),
+        }
+    }
+}
+
+/// Run a subcommand to completion and return the process exit code
+pub fn run(command: Command) -> i32 {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            println!("{}", json!({ "error": e.to_string() }));
+            return 1;
+        }
+    };
+
+    match runtime.block_on(execute(command)) {
+        Ok(output) => {
+            println!("{}", output);
+            0
+        }
+        Err(e) => {
+            println!("{}", json!({ "error": e }));
+            1
+        }
+    }
+}
+
+async fn execute(command: Command) -> Result<Value, String> {
+    match command {
+        Command::Watch => watch().await,
+        Command::Organize { dir, dry_run } => organize(&dir, dry_run).await,
+        Command::Undo => undo_last(),
+        Command::Status => status(),
+        Command::Login { email, password } => login(email, password).await,
+        Command::Ctl { method, params } => ctl(&method, params.as_deref()).await,
+        Command::Export {
+            kind,
+            format,
+            from,
+            to,
+            output,
+        } => export(kind, format, from, to, &output),
+        Command::Rules {
+            command: RulesCommand::Sync,
+        } => sync_rules().await,
+    }
+}
+
+async fn watch() -> Result<Value, String> {
+    // Two watchers on the same folders would race to move the same files
+    let _lock = instance::acquire(Role::Headless)?
+        .ok_or("Another FileSorter agent is already watching this config directory")?;
+
+    let engine = Engine::load()?;
+    let settings = engine.settings()?;
+    let folders = settings.folders.clone();
+
+    let watcher = FileWatcher::new(
+        settings,
+        None,
+        Arc::new(Mutex::new(false)),
+        engine.storage.clone(),
+        engine.duplicate_index.clone(),
+    );
+    watcher.start().await.map_err(|e| e.to_string())?;
+
+    println!(
+        "{}",
+        json!({
+            "event": "watching",
+            "profile": engine.config.active_profile,
+            "folders": folders,
+        })
+    );
+
+    shutdown_signal().await;
+    drop(watcher);
+
+    Ok(json!({ "event": "stopped" }))
+}
+
+/// Wait for Ctrl+C, or SIGTERM from a service manager
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+async fn organize(dir: &Path, dry_run: bool) -> Result<Value, String> {
+    let engine = Engine::load()?;
+    let mut settings = engine.settings()?;
+    // Naming the folder in a terminal is as good as picking it in the app
+    settings.policy = settings.policy.with_root(dir);
+    file_watcher::organize_folder(dir, dry_run, &settings, &engine.context()).await
+}
+
+fn undo_last() -> Result<Value, String> {
+    let engine = Engine::load()?;
+    let mut storage = engine.storage.lock().map_err(|e| e.to_string())?;
+    let mut index = engine.duplicate_index.lock().map_err(|e| e.to_string())?;
+
+    let record = undo::undo_last(&mut storage, &mut index)?;
+    Ok(json!({ "undone": record }))
+}
+
+fn status() -> Result<Value, String> {
+    let engine = Engine::load()?;
+    let config = &engine.config;
+    let storage = engine.storage.lock().map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "config_path": AppConfig::config_path().to_string_lossy(),
+        "profile": config.active_profile,
+        "api_url": config.api_url,
+        "is_logged_in": config.access_token.is_some(),
+        "watched_folders": config.enabled_folders(),
+        "cached_rules": storage.cached_rules(&config.active_profile).len(),
+        "files_today": statistics::files_today(&storage),
+        "last_move": storage.last_move(),
+    }))
+}
+
+async fn login(email: String, password: Option<String>) -> Result<Value, String> {
+    let password = match password.or_else(|| std::env::var(PASSWORD_ENV).ok()) {
+        Some(password) => password,
+        None => rpassword::prompt_password("Password: ").map_err(|e| e.to_string())?,
+    };
+
+    let mut engine = Engine::load()?;
+    let result = api_client::login(&engine.config.api_url, &email, &password).await?;
+
+    let token = |key: &str| {
+        result[key]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("Login response has no {}", key))
+    };
+    engine.config.access_token = Some(token("access_token")?);
+    engine.config.refresh_token = Some(token("refresh_token")?);
+    engine.config.save().map_err(|e| e.to_string())?;
+
+    Ok(json!({
+        "is_logged_in": true,
+        "email": email,
+        "profile": engine.config.active_profile,
+    }))
+}
+
+async fn ctl(method: &str, params: Option<&str>) -> Result<Value, String> {
+    let params = match params {
+        Some(params) => serde_json::from_str(params).map_err(|e| format!("Invalid params: {}", e))?,
+        None => Value::Null,
+    };
+    let timeout = match method {
+        "organize" => control::ORGANIZE_TIMEOUT,
+        _ => control::CALL_TIMEOUT,
+    };
+    control::call(method, params, timeout).await
+}
+
+fn export(
+    kind: ExportKind,
+    format: ExportFormat,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    output: &Path,
+) -> Result<Value, String> {
+    let engine = Engine::load()?;
+    let watched = engine.config.enabled_folders();
+    let collected = {
+        let storage = engine.storage.lock().map_err(|e| e.to_string())?;
+        export::Export::collect(&storage, kind, from, to, &watched)
+    };
+
+    let rows = collected.write(format, output)?;
+    Ok(json!({ "path": output.to_string_lossy(), "rows": rows }))
+}
+
+async fn sync_rules() -> Result<Value, String> {
+    let engine = Engine::load()?;
+    let settings = engine.settings()?;
+
+    let response = api_client::get_rules(&settings.api_url, &settings.token).await?;
+
+    // The server answers with either a bare list or `{"rules": [...]}`
+    let list = match response {
+        Value::Object(mut object) => object.remove("rules").unwrap_or(Value::Null),
+        other => other,
+    };
+    let rules: Vec<LocalRule> =
+        serde_json::from_value(list).map_err(|e| format!("Unexpected rules response: {}", e))?;
+
+    let count = rules.len();
+    let profile = engine.config.active_profile.clone();
+    engine
+        .storage
+        .lock()
+        .map_err(|e| e.to_string())?
+        .cache_rules(&profile, rules);
+
+    Ok(json!({ "profile": profile, "rules": count }))
+}
@@ -0,0 +1,251 @@
+//! Browsing the move history, backed by the local journal.
+//!
+//! Local entries are authoritative and support filters, sorting and paging.
+//! Actions only the server knows about (made on another machine, or before
+//! the journal existed) are browsed separately, page by page as the server
+//! returns them, since they cannot be filtered or counted locally.
+
+use chrono::{DateTime, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_client;
+use crate::statistics::local_midnight;
+use crate::storage::{LocalStorage, MoveOrder, MoveQuery, MoveRecord};
+
+const DEFAULT_PER_PAGE: u32 = 20;
+const MAX_PER_PAGE: u32 = 200;
+
+/// Parameters of `get_history`; all filters are optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// 1-based
+    pub page: u32,
+    pub per_page: u32,
+    /// Part of the file name
+    pub search: Option<String>,
+    pub category: Option<String>,
+    pub folder: Option<String>,
+    pub method: Option<String>,
+    /// Local dates, inclusive
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub sort: MoveOrder,
+    pub include_undone: bool,
+}
+
+impl HistoryQuery {
+    fn page(&self) -> u32 {
+        self.page.max(1)
+    }
+
+    fn per_page(&self) -> u32 {
+        match self.per_page {
+            0 => DEFAULT_PER_PAGE,
+            n => n.min(MAX_PER_PAGE),
+        }
+    }
+
+    fn to_move_query(&self) -> MoveQuery {
+        let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.trim().is_empty());
+
+        MoveQuery {
+            search: non_empty(&self.search),
+            category: non_empty(&self.category),
+            folder: non_empty(&self.folder),
+            method: non_empty(&self.method),
+            from: self.from.and_then(local_midnight),
+            to: self.to.and_then(|day| local_midnight(day + Duration::days(1))),
+            include_undone: self.include_undone,
+            order: self.sort,
+            limit: self.per_page(),
+            offset: (self.page() - 1).saturating_mul(self.per_page()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    Local,
+    Server,
+}
+
+/// One move, from either history
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub origin: Origin,
+    /// Journal id for local entries, server id for server entries
+    pub id: String,
+    pub filename: String,
+    pub source_path: Option<String>,
+    pub dest_path: String,
+    pub category: Option<String>,
+    pub category_icon: Option<String>,
+    pub rule_id: Option<String>,
+    pub rule_name: Option<String>,
+    pub confidence: Option<f64>,
+    pub method: Option<String>,
+    pub size_bytes: Option<u64>,
+    /// Unix seconds
+    pub timestamp: i64,
+    pub undone: bool,
+}
+
+impl From<MoveRecord> for HistoryEntry {
+    fn from(record: MoveRecord) -> Self {
+        Self {
+            origin: Origin::Local,
+            id: record.id.to_string(),
+            filename: record.filename,
+            source_path: Some(record.source_path),
+            dest_path: record.dest_path,
+            category: record.category,
+            category_icon: None,
+            rule_id: record.rule_id,
+            rule_name: record.rule_name,
+            confidence: record.confidence,
+            method: Some(record.method),
+            size_bytes: Some(record.size_bytes),
+            timestamp: record.timestamp,
+            undone: record.undone,
+        }
+    }
+}
+
+impl HistoryEntry {
+    /// Read an action from `/api/history`; `None` if it lacks the basics
+    fn from_server(action: &Value) -> Option<Self> {
+        let text = |key: &str| action[key].as_str().map(str::to_string);
+        let id = match &action["id"] {
+            Value::String(id) => id.clone(),
+            Value::Number(id) => id.to_string(),
+            _ => return None,
+        };
+
+        Some(Self {
+            origin: Origin::Server,
+            id,
+            filename: text("filename")?,
+            source_path: text("source_path"),
+            dest_path: text("dest_path")?,
+            category: text("category"),
+            category_icon: text("category_icon"),
+            rule_id: text("rule_id"),
+            rule_name: None,
+            confidence: action["confidence"].as_f64(),
+            method: None,
+            size_bytes: None,
+            timestamp: DateTime::parse_from_rfc3339(&text("created_at")?).ok()?.timestamp(),
+            undone: false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Entries matching the filters, across all pages
+    pub total: u64,
+}
+
+/// Query the local journal
+pub fn local_page(storage: &LocalStorage, query: &HistoryQuery) -> HistoryPage {
+    let (records, total) = storage.search_moves(&query.to_move_query());
+    HistoryPage {
+        entries: records.into_iter().map(HistoryEntry::from).collect(),
+        page: query.page(),
+        per_page: query.per_page(),
+        total,
+    }
+}
+
+/// One page of server history, without what the journal already has
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerHistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Whether the server returned a full page, so there may be another
+    pub has_more: bool,
+}
+
+/// Fetch a page of server history
+///
+/// Pages follow the server's own paging, so one may show fewer entries than
+/// `per_page` once those known locally are left out.
+pub async fn server_page(
+    api_url: &str,
+    token: &str,
+    query: &HistoryQuery,
+    storage: &std::sync::Mutex<LocalStorage>,
+) -> Result<ServerHistoryPage, String> {
+    let response = api_client::get_history(api_url, token, query.page(), query.per_page()).await?;
+    let actions = response["actions"].as_array().cloned().unwrap_or_default();
+
+    let storage = storage.lock().map_err(|e| e.to_string())?;
+    Ok(ServerHistoryPage {
+        entries: server_only(&actions, |entry| storage.has_move_to(&entry.dest_path)),
+        page: query.page(),
+        per_page: query.per_page(),
+        has_more: actions.len() >= query.per_page() as usize,
+    })
+}
+
+/// Readable server actions that `known_locally` does not claim
+fn server_only(actions: &[Value], known_locally: impl Fn(&HistoryEntry) -> bool) -> Vec<HistoryEntry> {
+    actions
+        .iter()
+        .filter_map(HistoryEntry::from_server)
+        .filter(|entry| !known_locally(entry))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn paging_is_clamped() {
+        let query = HistoryQuery::default().to_move_query();
+        assert_eq!((query.limit, query.offset), (DEFAULT_PER_PAGE, 0));
+
+        let query = HistoryQuery { page: 3, per_page: 1000, ..Default::default() }.to_move_query();
+        assert_eq!((query.limit, query.offset), (MAX_PER_PAGE, 2 * MAX_PER_PAGE));
+
+        let query = HistoryQuery { page: u32::MAX, per_page: 50, ..Default::default() }.to_move_query();
+        assert_eq!(query.offset, u32::MAX);
+    }
+
+    #[test]
+    fn blank_filters_are_ignored() {
+        let query = HistoryQuery {
+            search: Some("  ".to_string()),
+            category: Some(String::new()),
+            ..Default::default()
+        }
+        .to_move_query();
+        assert!(query.search.is_none() && query.category.is_none());
+    }
+
+    #[test]
+    fn server_only_keeps_readable_unknown_actions() {
+        let actions = vec![
+            json!({ "id": 7, "filename": "a.pdf", "dest_path": "/d/a.pdf", "created_at": "2024-05-01T10:00:00Z" }),
+            json!({ "id": "x", "filename": "b.pdf", "dest_path": "/d/b.pdf", "created_at": "2024-05-01T11:00:00Z" }),
+            json!({ "id": 9, "filename": "c.pdf", "dest_path": "/d/c.pdf", "created_at": "yesterday" }),
+            json!({ "filename": "d.pdf", "dest_path": "/d/d.pdf", "created_at": "2024-05-01T12:00:00Z" }),
+        ];
+
+        let entries = server_only(&actions, |entry| entry.dest_path == "/d/b.pdf");
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "7");
+        assert_eq!(entries[0].timestamp, 1714557600);
+        assert!(matches!(entries[0].origin, Origin::Server));
+    }
+}
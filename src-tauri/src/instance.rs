@@ -0,0 +1,133 @@
+//! Making sure only one agent watches a config directory at a time.
+//!
+//! The first agent holds an exclusive lock on `agent.lock`. A later launch
+//! that cannot take the lock hands its arguments to the running agent over
+//! the control socket and exits. A headless `watch` holds the same lock but
+//! has no socket, so the holder records its role next to the lock.
+
+use clap::Parser;
+use serde_json::json;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::time::Duration;
+
+use crate::cli::Cli;
+use crate::config::AppConfig;
+use crate::control;
+
+/// How long a second launch waits for a starting agent to open its socket
+const FORWARD_ATTEMPTS: u32 = 10;
+const FORWARD_RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Which kind of agent holds the lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The tray app, which serves the control socket
+    Window,
+    /// `filesorter watch`, which runs without a socket
+    Headless,
+}
+
+impl Role {
+    fn as_str(self) -> &'static str {
+        match self {
+            Role::Window => "window",
+            Role::Headless => "headless",
+        }
+    }
+}
+
+/// Held for the lifetime of the primary agent; the OS releases it on exit
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Take the instance lock, or return `None` if another agent holds it
+pub fn acquire(role: Role) -> Result<Option<InstanceLock>, String> {
+    let path = AppConfig::config_dir().join("agent.lock");
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => return Ok(None),
+        Err(TryLockError::Error(e)) => return Err(format!("Cannot lock {}: {}", path.display(), e)),
+    }
+
+    // The pid is only informational, for whoever looks at the file
+    file.set_len(0).ok();
+    write!(file, "{}", std::process::id()).ok();
+    // Kept in a separate file because Windows will not let others read a locked one
+    std::fs::write(role_path(), role.as_str()).ok();
+
+    Ok(Some(InstanceLock { _file: file }))
+}
+
+fn role_path() -> std::path::PathBuf {
+    AppConfig::config_dir().join("agent.role")
+}
+
+/// Role of the agent currently holding the lock, as it recorded it
+pub fn holder() -> Option<Role> {
+    match std::fs::read_to_string(role_path()).ok()?.trim() {
+        "window" => Some(Role::Window),
+        "headless" => Some(Role::Headless),
+        _ => None,
+    }
+}
+
+/// Pass this launch's arguments to the running agent; returns an exit code
+pub fn forward_to_primary() -> i32 {
+    if holder() == Some(Role::Headless) {
+        eprintln!("FileSorter is already running headless (`filesorter watch`); stop it before opening the app");
+        return 1;
+    }
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cwd = std::env::current_dir().ok();
+    let params = json!({ "args": args, "cwd": cwd });
+
+    // The reply to a forwarded --organize only comes once the folder is sorted
+    let (lenient_args, _) = Cli::without_stray_positionals(std::env::args_os());
+    let timeout = match Cli::try_parse_from(lenient_args) {
+        Ok(cli) if cli.organize.is_some() => control::ORGANIZE_TIMEOUT,
+        _ => control::CALL_TIMEOUT,
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("FileSorter: {}", e);
+            return 1;
+        }
+    };
+
+    let result = runtime.block_on(async {
+        let mut last_error = String::new();
+        for _ in 0..FORWARD_ATTEMPTS {
+            match control::call("forward_args", params.clone(), timeout).await {
+                Ok(result) => return Ok(result),
+                // Only a socket that is not open yet is worth waiting for
+                Err(e) if e.starts_with(control::NOT_RUNNING) => last_error = e,
+                Err(e) => return Err(e),
+            }
+            tokio::time::sleep(FORWARD_RETRY_DELAY).await;
+        }
+        Err(last_error)
+    });
+
+    match result {
+        Ok(_) => {
+            log::info!("FileSorter is already running, arguments forwarded");
+            0
+        }
+        Err(e) => {
+            eprintln!("FileSorter is already running but did not respond: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Logging to stderr and to a size-rotated JSON Lines file.
+//!
+//! The file lives in `<config dir>/logs/filesorter.log`. Once it grows past
+//! [`MAX_FILE_BYTES`] it is renamed to `filesorter.log.1`, older files shift
+//! up by one and anything beyond [`KEPT_FILES`] is dropped. Each line is an
+//! object with `ts`, `level`, `target`, `message` and, while a file is being
+//! handled, the `file_id` that ties together everything logged about it.
+//!
+//! The tray app and a headless agent may share the directory, so before
+//! rotating a process checks whether the other one already did.
+//!
+//! `RUST_LOG` filters stderr as before; `FILESORTER_LOG` sets the level
+//! written to the file and defaults to `info`.
+
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::config::AppConfig;
+
+const FILE_NAME: &str = "filesorter.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEPT_FILES: usize = 3;
+
+tokio::task_local! {
+    static FILE_ID: String;
+}
+
+/// Run `future` with every log line it writes tagged with a fresh file id
+pub async fn with_file_id<F: Future>(future: F) -> F::Output {
+    let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    FILE_ID.scope(id, future).await
+}
+
+/// Id of the file the current task is handling, if any
+pub fn current_file_id() -> Option<String> {
+    FILE_ID.try_with(|id| id.clone()).ok()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub ts: DateTime<Local>,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_id: Option<String>,
+}
+
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    /// Bytes in the file as of the last check, plus what this process wrote
+    size: u64,
+}
+
+/// Opened by [`open_file`]; until then lines only go to stderr
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+struct Logger {
+    stderr: env_logger::Logger,
+    file_level: LevelFilter,
+}
+
+impl Logger {
+    fn write_line(&self, line: &str) {
+        let Ok(mut guard) = LOG_FILE.lock() else {
+            return;
+        };
+        let Some(log_file) = guard.as_mut() else {
+            return;
+        };
+
+        if log_file.size + line.len() as u64 > MAX_FILE_BYTES {
+            if let Err(e) = make_room(log_file, line.len() as u64) {
+                eprintln!("Cannot rotate log file: {}", e);
+                *guard = None;
+                return;
+            }
+        }
+
+        if log_file.file.write_all(line.as_bytes()).is_ok() {
+            log_file.size += line.len() as u64;
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.file_level || self.stderr.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+        if record.level() > self.file_level {
+            return;
+        }
+
+        let entry = LogEntry {
+            ts: Local::now(),
+            level: record.level().as_str().to_lowercase(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            file_id: current_file_id(),
+        };
+        if let Ok(mut line) = serde_json::to_string(&entry) {
+            line.push('\n');
+            self.write_line(&line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut guard) = LOG_FILE.lock() {
+            if let Some(log_file) = guard.as_mut() {
+                log_file.file.flush().ok();
+            }
+        }
+    }
+}
+
+fn log_dir() -> PathBuf {
+    AppConfig::config_dir().join("logs")
+}
+
+/// Current file first, then `.1`, `.2` and so on
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    std::iter::once(dir.join(FILE_NAME))
+        .chain((1..=KEPT_FILES).map(|n| dir.join(format!("{}.{}", FILE_NAME, n))))
+        .collect()
+}
+
+fn open_current(dir: &Path) -> std::io::Result<LogFile> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(FILE_NAME))?;
+    let size = file.metadata()?.len();
+    Ok(LogFile {
+        dir: dir.to_path_buf(),
+        file,
+        size,
+    })
+}
+
+/// Get the current file ready for `len` more bytes, rotating if it is full
+///
+/// Our handle may point at a file another process has already rotated away,
+/// and the size we track misses what the other process wrote, so both are
+/// checked against the path first.
+fn make_room(log_file: &mut LogFile, len: u64) -> std::io::Result<()> {
+    let ours = log_file.file.metadata()?.len();
+    let on_disk = fs::metadata(log_file.dir.join(FILE_NAME)).map(|m| m.len()).ok();
+
+    if on_disk == Some(ours) {
+        log_file.size = ours;
+    } else {
+        *log_file = open_current(&log_file.dir)?;
+    }
+
+    if log_file.size + len > MAX_FILE_BYTES {
+        *log_file = rotate(&log_file.dir)?;
+    }
+    Ok(())
+}
+
+fn rotate(dir: &Path) -> std::io::Result<LogFile> {
+    let files = log_files(dir);
+    fs::remove_file(&files[KEPT_FILES]).ok();
+    for n in (0..KEPT_FILES).rev() {
+        if files[n].exists() {
+            fs::rename(&files[n], &files[n + 1])?;
+        }
+    }
+    open_current(dir)
+}
+
+/// Install the logger; call once, first thing in `main`
+pub fn init() {
+    let stderr = env_logger::Builder::from_default_env().build();
+    let file_level = std::env::var("FILESORTER_LOG")
+        .ok()
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(LevelFilter::Info);
+
+    let max_level = file_level.max(stderr.filter());
+    if log::set_boxed_logger(Box::new(Logger { stderr, file_level })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Start writing the log file; call once the config directory is known
+pub fn open_file() {
+    match open_current(&log_dir()) {
+        Ok(file) => *LOG_FILE.lock().unwrap() = Some(file),
+        Err(e) => log::warn!("Cannot open log file in {}: {}", log_dir().display(), e),
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogQuery {
+    /// Least severe level to include, e.g. `warn` also returns errors
+    pub level: Option<String>,
+    pub from: Option<DateTime<Local>>,
+    pub to: Option<DateTime<Local>>,
+    pub file_id: Option<String>,
+    /// Case-insensitive text to look for in the message
+    pub search: Option<String>,
+    /// Newest entries to return, 500 by default
+    pub limit: Option<usize>,
+}
+
+/// Read matching entries from the current and rotated files, oldest first
+pub fn read(query: &LogQuery) -> Result<Vec<LogEntry>, String> {
+    log::logger().flush();
+    read_dir(&log_dir(), query)
+}
+
+fn read_dir(dir: &Path, query: &LogQuery) -> Result<Vec<LogEntry>, String> {
+    let level = match &query.level {
+        Some(level) => Some(Level::from_str(level).map_err(|_| format!("Unknown log level: {}", level))?),
+        None => None,
+    };
+    let search = query.search.as_ref().map(|s| s.to_lowercase());
+    let limit = query.limit.unwrap_or(500);
+
+    let mut entries = Vec::new();
+    for path in log_files(dir).iter().rev() {
+        let Ok(file) = File::open(path) else {
+            continue;
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let Ok(entry) = serde_json::from_str::<LogEntry>(&line) else {
+                continue;
+            };
+            let matches = level.is_none_or(|level| {
+                Level::from_str(&entry.level).is_ok_and(|entry_level| entry_level <= level)
+            }) && query.from.is_none_or(|from| entry.ts >= from)
+                && query.to.is_none_or(|to| entry.ts <= to)
+                && query
+                    .file_id
+                    .as_ref()
+                    .is_none_or(|id| entry.file_id.as_ref() == Some(id))
+                && search
+                    .as_ref()
+                    .is_none_or(|s| entry.message.to_lowercase().contains(s.as_str()));
+            if matches {
+                entries.push(entry);
+            }
+        }
+    }
+
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.split_off(skip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("filesorter-{}-{}", name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn line(level: &str, message: &str, file_id: Option<&str>) -> String {
+        let entry = LogEntry {
+            ts: Local::now(),
+            level: level.to_string(),
+            target: "test".to_string(),
+            message: message.to_string(),
+            file_id: file_id.map(str::to_string),
+        };
+        format!("{}\n", serde_json::to_string(&entry).unwrap())
+    }
+
+    #[test]
+    fn rotate_shifts_files_and_drops_the_oldest() {
+        let dir = temp_dir("rotate");
+        let files = log_files(&dir);
+        for (n, path) in files.iter().enumerate() {
+            fs::write(path, n.to_string()).unwrap();
+        }
+
+        let current = rotate(&dir).unwrap();
+
+        assert_eq!(current.size, 0);
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "");
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "0");
+        assert_eq!(fs::read_to_string(&files[2]).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&files[3]).unwrap(), "2");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn make_room_follows_a_rotation_by_another_process() {
+        let dir = temp_dir("shared");
+        let mut ours = open_current(&dir).unwrap();
+        ours.file.write_all(b"ours\n").unwrap();
+
+        // The other process rotates and starts a fresh file
+        let mut theirs = rotate(&dir).unwrap();
+        theirs.file.write_all(b"theirs\n").unwrap();
+
+        make_room(&mut ours, 1).unwrap();
+        ours.file.write_all(b"ours again\n").unwrap();
+
+        let files = log_files(&dir);
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), "theirs\nours again\n");
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "ours\n");
+        assert!(!files[2].exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn make_room_rotates_a_full_file() {
+        let dir = temp_dir("full");
+        let mut current = open_current(&dir).unwrap();
+        current.file.write_all(b"old\n").unwrap();
+
+        make_room(&mut current, MAX_FILE_BYTES).unwrap();
+
+        let files = log_files(&dir);
+        assert_eq!(current.size, 0);
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "old\n");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn read_filters_across_files_oldest_first() {
+        let dir = temp_dir("read");
+        let files = log_files(&dir);
+        fs::write(
+            &files[1],
+            line("error", "first failure", Some("aaaa")) + &line("info", "routine", None),
+        )
+        .unwrap();
+        fs::write(
+            &files[0],
+            line("warn", "Disk nearly full", None) + "not json\n" + &line("debug", "detail", Some("aaaa")),
+        )
+        .unwrap();
+
+        let messages = |query: LogQuery| -> Vec<String> {
+            read_dir(&dir, &query).unwrap().into_iter().map(|e| e.message).collect()
+        };
+
+        assert_eq!(messages(LogQuery::default()), ["first failure", "routine", "Disk nearly full", "detail"]);
+        assert_eq!(
+            messages(LogQuery { level: Some("warn".to_string()), ..Default::default() }),
+            ["first failure", "Disk nearly full"]
+        );
+        assert_eq!(
+            messages(LogQuery { file_id: Some("aaaa".to_string()), ..Default::default() }),
+            ["first failure", "detail"]
+        );
+        assert_eq!(
+            messages(LogQuery { search: Some("DISK".to_string()), ..Default::default() }),
+            ["Disk nearly full"]
+        );
+        assert_eq!(
+            messages(LogQuery { limit: Some(2), ..Default::default() }),
+            ["Disk nearly full", "detail"]
+        );
+        assert!(read_dir(&dir, &LogQuery { level: Some("loud".to_string()), ..Default::default() }).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -4,28 +4,62 @@
 )]
 
 mod api_client;
+mod autostart;
 mod file_watcher;
 mod classifier;
+mod cli;
 mod config;
+mod config_overrides;
+mod config_validation;
+mod config_watcher;
+mod control;
+mod duplicates;
+mod events;
+mod export;
+mod fs_util;
+mod headless;
+mod history;
+mod instance;
+mod logging;
+mod path_guard;
+mod pause;
+mod similar_images;
+mod statistics;
 mod storage;
+mod undo;
 
 use std::fs;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tauri::api::dialog::blocking::FileDialogBuilder;
 use tauri::{
     CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+    SystemTraySubmenu,
     WindowEvent,
 };
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
-use crate::config::AppConfig;
-use crate::file_watcher::FileWatcher;
+use crate::api_client::ActionLogRequest;
+use crate::classifier::LocalClassifier;
+use crate::config::{AppConfig, ConfigRecovery};
+use crate::config_validation::ValidationReport;
+use crate::duplicates::DuplicateIndex;
+use crate::file_watcher::{FileWatcher, WatcherSettings};
+use crate::path_guard::PathPolicy;
+use crate::storage::{DailyStats, LocalStorage, MoveRecord, QueuedFile};
 
 pub struct AppState {
     pub config: Arc<Mutex<AppConfig>>,
     pub watcher: Arc<Mutex<Option<FileWatcher>>>,
     pub is_paused: Arc<Mutex<bool>>,
-    pub files_today: Arc<Mutex<u32>>,
+    /// When a timed pause ends, if one is active
+    pub paused_until: Arc<Mutex<Option<chrono::DateTime<chrono::Local>>>>,
+    pub duplicate_index: Arc<Mutex<DuplicateIndex>>,
+    pub storage: Arc<Mutex<LocalStorage>>,
+    pub config_recovery: Arc<Mutex<Option<ConfigRecovery>>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +69,19 @@ pub struct FileInfoForOrganize {
     pub size_bytes: u64,
     pub path: String,
     pub modified: Option<String>,
+    /// Path of an existing file with identical content
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileContent {
+    /// Base64-encoded bytes starting at `offset`
+    pub data: String,
+    pub offset: u64,
+    pub total_size: u64,
+    /// Base64-encoded bytes from the end of the file, when requested
+    pub tail: Option<String>,
+    pub tail_offset: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,32 +100,126 @@ pub struct MoveResult {
 }
 
 fn main() {
-    env_logger::init();
+    logging::init();
+
+    let mut cli = cli::Cli::parse_lenient();
+    config_overrides::init(&cli);
+    logging::open_file();
+
+    if let Some(command) = cli.command.take() {
+        std::process::exit(headless::run(command));
+    }
+
+    // Held until the process exits
+    let _instance_lock = match instance::acquire(instance::Role::Window) {
+        Ok(Some(lock)) => Some(lock),
+        Ok(None) => std::process::exit(instance::forward_to_primary()),
+        Err(e) => {
+            log::warn!("Cannot check for a running instance: {}", e);
+            None
+        }
+    };
 
-    let config = AppConfig::load().unwrap_or_default();
+    let (config, config_recovery) = match AppConfig::load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log::error!("Failed to load config, falling back to defaults: {}", e);
+            let recovery = ConfigRecovery {
+                reason: e.to_string(),
+                restored_from: None,
+                corrupt_copy: None,
+            };
+            (AppConfig::default(), Some(recovery))
+        }
+    };
+    
+    let report = config_validation::validate(&config);
+    for issue in &report.errors {
+        log::warn!("Config error in {}: {}", issue.field, issue.message);
+    }
+    for issue in &report.warnings {
+        log::info!("Config warning in {}: {}", issue.field, issue.message);
+    }
+    
+    let storage = LocalStorage::open().unwrap_or_else(|e| {
+        log::error!("Failed to open local storage, history will not be kept: {}", e);
+        LocalStorage::in_memory()
+    });
     
     let app_state = AppState {
         config: Arc::new(Mutex::new(config)),
         watcher: Arc::new(Mutex::new(None)),
         is_paused: Arc::new(Mutex::new(false)),
-        files_today: Arc::new(Mutex::new(0)),
+        paused_until: Arc::new(Mutex::new(None)),
+        duplicate_index: Arc::new(Mutex::new(DuplicateIndex::load())),
+        storage: Arc::new(Mutex::new(storage)),
+        config_recovery: Arc::new(Mutex::new(config_recovery)),
     };
 
-    let tray_menu = create_tray_menu(false, 0);
+    let tray_menu = create_tray_menu(
+        false,
+        None,
+        statistics::files_today(&app_state.storage.lock().unwrap()),
+        &app_state.config.lock().unwrap().active_profile,
+    );
     let system_tray = SystemTray::new().with_menu(tray_menu);
 
     tauri::Builder::default()
         .system_tray(system_tray)
         .on_system_tray_event(handle_tray_event)
         .manage(app_state)
+        .setup(move |app| {
+            if cli.minimized {
+                if let Some(window) = app.get_window("main") {
+                    let _ = window.hide();
+                }
+            }
+
+            let start_on_boot = app.state::<AppState>().config.lock().unwrap().start_on_boot;
+            autostart::reconcile(start_on_boot);
+            pause::restore(&app.handle());
+
+            let handle = app.handle();
+            api_client::on_connectivity_change(move |online| {
+                let payload = events::ConnectivityChanged { online };
+                events::emit(&handle, events::CONNECTIVITY_CHANGED, payload);
+
+                // Report what was sorted while the server was out of reach
+                let state = handle.state::<AppState>();
+                if let (true, Ok((api_url, token))) = (online, api_credentials(&state)) {
+                    let storage = state.storage.clone();
+                    tauri::async_runtime::spawn(file_watcher::send_pending_actions(api_url, token, storage));
+                }
+            });
+
+            if let Some(dir) = cli.organize.clone() {
+                tauri::async_runtime::spawn(control::organize_from_launch(app.handle(), dir));
+            }
+
+            notify_config_recovery(&app.handle());
+            statistics::spawn_midnight_rollover(app.handle());
+            config_watcher::spawn(app.handle());
+            control::spawn(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_status,
             get_config,
             save_config,
+            dismiss_config_recovery,
             login,
             logout,
             toggle_pause,
-            get_recent_actions,
+            pause_for,
+            get_paused_queue,
+            get_daily_history,
+            get_statistics,
+            get_history,
+            get_server_history,
+            export_history,
+            get_logs,
+            sort_paused_files,
+            discard_paused_files,
             open_dashboard,
             start_watching,
             stop_watching,
@@ -88,69 +229,134 @@ fn main() {
             read_file_content,
             execute_file_moves,
             get_user_folders,
-            get_access_token,
+            pick_folder,
+            find_similar_images,
+            get_user_stats,
+            extract_content,
+            analyze_organize,
+            generate_rules,
+            log_organize_action,
+            list_profiles,
+            create_profile,
+            switch_profile,
         ])
-        .on_window_event(|event| match event.event() {
-            WindowEvent::CloseRequested { api, .. } => {
+        .on_window_event(|event| {
+            if let WindowEvent::CloseRequested { api, .. } = event.event() {
                 // Hide window instead of closing
                 event.window().hide().unwrap();
                 api.prevent_close();
             }
-            _ => {}
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-fn create_tray_menu(is_paused: bool, files_count: u32) -> SystemTrayMenu {
-    let status = if is_paused { "⏸️ На паузе" } else { "✅ Активен" };
-    let status_item = CustomMenuItem::new("status", format!("{} • {} файлов сегодня", status, files_count)).disabled();
+/// Tell the user if their config had to be restored at startup
+fn notify_config_recovery(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(recovery) = state.config_recovery.lock().ok().and_then(|r| r.clone()) else {
+        return;
+    };
+
+    let body = if recovery.restored_from.is_some() {
+        "Файл настроек был повреждён, восстановлена резервная копия"
+    } else {
+        "Файл настроек был повреждён, настройки сброшены"
+    };
+
+    let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title("FileSorter")
+        .body(body)
+        .show();
+}
+
+fn create_tray_menu(
+    is_paused: bool,
+    paused_until: Option<chrono::DateTime<chrono::Local>>,
+    files_count: u32,
+    profile: &str,
+) -> SystemTrayMenu {
+    let status = match (is_paused, paused_until) {
+        (false, _) => "✅ Активен".to_string(),
+        (true, None) => "⏸️ На паузе".to_string(),
+        (true, Some(deadline)) => format!("⏸️ На паузе, {}", pause::format_remaining(deadline)),
+    };
+    let status_item = CustomMenuItem::new(
+        "status",
+        format!("{} • {} • {} файлов сегодня", status, profile, files_count),
+    )
+    .disabled();
     
-    let pause_text = if is_paused { "▶️ Продолжить" } else { "⏸️ Пауза" };
-    let pause = CustomMenuItem::new("pause", pause_text);
     let settings = CustomMenuItem::new("settings", "⚙️ Настройки");
     let dashboard = CustomMenuItem::new("dashboard", "🌐 Открыть Dashboard");
     let quit = CustomMenuItem::new("quit", "❌ Выход");
 
-    SystemTrayMenu::new()
+    let menu = SystemTrayMenu::new()
         .add_item(status_item)
-        .add_native_item(SystemTrayMenuItem::Separator)
-        .add_item(pause)
-        .add_item(settings)
+        .add_native_item(SystemTrayMenuItem::Separator);
+
+    let menu = if is_paused {
+        menu.add_item(CustomMenuItem::new("pause", "▶️ Продолжить"))
+    } else {
+        let durations = SystemTrayMenu::new()
+            .add_item(CustomMenuItem::new("pause_15m", "15 минут"))
+            .add_item(CustomMenuItem::new("pause_1h", "1 час"))
+            .add_item(CustomMenuItem::new("pause_tomorrow", "До завтра"))
+            .add_item(CustomMenuItem::new("pause", "Без срока"));
+        menu.add_submenu(SystemTraySubmenu::new("⏸️ Пауза", durations))
+    };
+
+    menu.add_item(settings)
         .add_item(dashboard)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit)
 }
 
+/// Show, unminimize and focus the main window
+pub(crate) fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Rebuild the tray menu from the current state
+pub(crate) fn refresh_tray_menu(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let is_paused = state.is_paused.lock().map(|p| *p).unwrap_or(false);
+    let paused_until = state.paused_until.lock().map(|p| *p).unwrap_or(None);
+    let files_count = state
+        .storage
+        .lock()
+        .map(|s| statistics::files_today(&s))
+        .unwrap_or(0);
+    let profile = state
+        .config
+        .lock()
+        .map(|c| c.active_profile.clone())
+        .unwrap_or_default();
+
+    let _ = app
+        .tray_handle()
+        .set_menu(create_tray_menu(is_paused, paused_until, files_count, &profile));
+}
+
 fn handle_tray_event(app: &tauri::AppHandle, event: SystemTrayEvent) {
     match event {
-        SystemTrayEvent::LeftClick { .. } => {
-            if let Some(window) = app.get_window("main") {
-                // Always try to show and focus the window
-                let _ = window.unminimize();
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
-        }
-        SystemTrayEvent::DoubleClick { .. } => {
-            if let Some(window) = app.get_window("main") {
-                let _ = window.unminimize();
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
+        SystemTrayEvent::LeftClick { .. } | SystemTrayEvent::DoubleClick { .. } => {
+            show_main_window(app);
         }
         SystemTrayEvent::MenuItemClick { id, .. } => {
             let state = app.state::<AppState>();
             
             match id.as_str() {
                 "pause" => {
-                    if let Ok(mut is_paused) = state.is_paused.lock() {
-                        *is_paused = !*is_paused;
-                        let files_count = state.files_today.lock().map(|f| *f).unwrap_or(0);
-                        let new_menu = create_tray_menu(*is_paused, files_count);
-                        let _ = app.tray_handle().set_menu(new_menu);
-                    }
+                    pause::toggle(app);
                 }
+                "pause_15m" => pause::pause_for(app, pause::PauseDuration::FifteenMinutes),
+                "pause_1h" => pause::pause_for(app, pause::PauseDuration::OneHour),
+                "pause_tomorrow" => pause::pause_for(app, pause::PauseDuration::UntilTomorrow),
                 "settings" => {
                     if let Some(window) = app.get_window("main") {
                         let _ = window.show();
@@ -175,38 +381,126 @@ fn handle_tray_event(app: &tauri::AppHandle, event: SystemTrayEvent) {
 
 #[tauri::command]
 fn get_status(state: tauri::State<AppState>) -> serde_json::Value {
+    status_json(&state)
+}
+
+/// Agent status, shared by the UI and the control socket
+pub(crate) fn status_json(state: &AppState) -> serde_json::Value {
     let is_paused = *state.is_paused.lock().unwrap();
-    let files_today = *state.files_today.lock().unwrap();
+    let paused_until = *state.paused_until.lock().unwrap();
+    let (files_today, paused_queue) = state
+        .storage
+        .lock()
+        .map(|s| (statistics::files_today(&s), s.paused_queue().len()))
+        .unwrap_or((0, 0));
     let config = state.config.lock().unwrap();
     
+    let config_recovery = state.config_recovery.lock().ok().and_then(|r| r.clone());
+    
     serde_json::json!({
         "is_paused": is_paused,
+        "paused_until": paused_until.map(|t| t.to_rfc3339()),
+        "paused_queue": paused_queue,
+        "is_online": api_client::is_online(),
         "files_today": files_today,
         "is_logged_in": config.access_token.is_some(),
-        "watched_folders": config.watched_folders.clone(),
+        "watched_folders": config.enabled_folders(),
+        "active_profile": config.active_profile,
+        "config_recovery": config_recovery,
     })
 }
 
+/// Config as shown to the UI; credentials never leave the backend
 #[tauri::command]
-fn get_access_token(state: tauri::State<AppState>) -> Option<String> {
+fn get_config(state: tauri::State<AppState>) -> serde_json::Value {
     let config = state.config.lock().unwrap();
-    config.access_token.clone()
+    serde_json::json!({
+        "config": config.redacted(),
+        "sources": config.sources(),
+    })
 }
 
 #[tauri::command]
-fn get_config(state: tauri::State<AppState>) -> serde_json::Value {
-    let config = state.config.lock().unwrap();
-    serde_json::to_value(&*config).unwrap()
+async fn save_config(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    mut config: AppConfig,
+) -> Result<ValidationReport, ValidationReport> {
+    let mut current_config = state
+        .config
+        .lock()
+        .map_err(|e| ValidationReport::failed("config", e.to_string()))?;
+
+    // Only the native folder dialog may grant new roots
+    config.approved_roots = current_config.approved_roots.clone();
+
+    // The UI only ever sees a redacted config, so tokens stay as they are
+    config.access_token = current_config.access_token.clone();
+    config.refresh_token = current_config.refresh_token.clone();
+
+    // Profiles are managed by their own commands
+    config.active_profile = current_config.active_profile.clone();
+    config.profiles = current_config.profiles.clone();
+
+    // Fields set from the environment or command line stay as launched
+    config
+        .keep_overridden(&current_config)
+        .map_err(|e| ValidationReport::failed("config", e.to_string()))?;
+
+    let mut report = config_validation::validate(&config);
+
+    let policy = PathPolicy::from_config(&current_config);
+    for (i, folder) in config.watched_folders.iter().enumerate() {
+        let is_new = !current_config.watched_folders.iter().any(|f| f.path == folder.path);
+        if is_new && Path::new(&folder.path).exists() {
+            if let Err(e) = policy.check(Path::new(&folder.path)) {
+                report.error(format!("watched_folders[{}].path", i), e);
+            }
+        }
+    }
+
+    if !report.is_valid() {
+        return Err(report);
+    }
+
+    if config.start_on_boot != current_config.start_on_boot {
+        autostart::apply(config.start_on_boot)
+            .map_err(|e| ValidationReport::failed("start_on_boot", e))?;
+    }
+
+    config
+        .save()
+        .map_err(|e| ValidationReport::failed("config", e.to_string()))?;
+    *current_config = config.clone();
+    drop(current_config);
+
+    // A config saved from the UI replaces whatever was recovered at startup
+    if let Ok(mut recovery) = state.config_recovery.lock() {
+        *recovery = None;
+    }
+
+    apply_config_to_watcher(&app, &config);
+    Ok(report)
 }
 
+/// Hide the startup config recovery notice for the rest of the session
 #[tauri::command]
-async fn save_config(state: tauri::State<'_, AppState>, config: AppConfig) -> Result<(), String> {
-    let mut current_config = state.config.lock().map_err(|e| e.to_string())?;
-    *current_config = config.clone();
-    config.save().map_err(|e| e.to_string())?;
+fn dismiss_config_recovery(state: tauri::State<AppState>) -> Result<(), String> {
+    *state.config_recovery.lock().map_err(|e| e.to_string())? = None;
     Ok(())
 }
 
+/// Push a changed config into the running watcher, if there is one
+pub(crate) fn apply_config_to_watcher(app: &tauri::AppHandle, config: &AppConfig) {
+    let state = app.state::<AppState>();
+    if let Ok(watcher) = state.watcher.lock() {
+        if let Some(watcher) = watcher.as_ref() {
+            watcher.apply_config(config);
+        }
+    }
+    events::watcher_state_changed(app);
+}
+
 #[tauri::command]
 async fn login(
     state: tauri::State<'_, AppState>,
@@ -218,7 +512,7 @@ async fn login(
         config.api_url.clone()
     };
 
-    let result = api_client::login(&api_url, &email, &password).await?;
+    let mut result = api_client::login(&api_url, &email, &password).await?;
     
     {
         let mut config = state.config.lock().map_err(|e| e.to_string())?;
@@ -227,6 +521,12 @@ async fn login(
         config.save().map_err(|e| e.to_string())?;
     }
 
+    // Tokens stay in the backend
+    if let Some(object) = result.as_object_mut() {
+        object.remove("access_token");
+        object.remove("refresh_token");
+    }
+
     Ok(result)
 }
 
@@ -240,32 +540,193 @@ async fn logout(state: tauri::State<'_, AppState>) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn toggle_pause(app: tauri::AppHandle, state: tauri::State<AppState>) -> bool {
-    let Ok(mut is_paused) = state.is_paused.lock() else {
-        return false;
-    };
-    *is_paused = !*is_paused;
-    
-    let files_count = state.files_today.lock().map(|f| *f).unwrap_or(0);
-    let new_menu = create_tray_menu(*is_paused, files_count);
-    let _ = app.tray_handle().set_menu(new_menu);
-    
-    *is_paused
+fn toggle_pause(app: tauri::AppHandle) -> bool {
+    pause::toggle(&app)
 }
 
+/// Per-day counters for the last `days` days (30 by default), oldest first
 #[tauri::command]
-async fn get_recent_actions(state: tauri::State<'_, AppState>) -> Result<serde_json::Value, String> {
-    let (api_url, token) = {
-        let config = state.config.lock().map_err(|e| e.to_string())?;
-        
-        if config.access_token.is_none() {
-            return Ok(serde_json::json!([]));
-        }
+fn get_daily_history(state: tauri::State<AppState>, days: Option<u32>) -> Result<Vec<DailyStats>, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    Ok(statistics::daily_history(&storage, days.unwrap_or(30)))
+}
+
+/// Browse the local move history
+#[tauri::command]
+fn get_history(
+    state: tauri::State<'_, AppState>,
+    query: Option<history::HistoryQuery>,
+) -> Result<history::HistoryPage, String> {
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    Ok(history::local_page(&storage, &query.unwrap_or_default()))
+}
+
+/// Browse actions the server has that the local journal does not
+#[tauri::command]
+async fn get_server_history(
+    state: tauri::State<'_, AppState>,
+    query: Option<history::HistoryQuery>,
+) -> Result<history::ServerHistoryPage, String> {
+    let (api_url, token) = api_credentials(&state)?;
+    history::server_page(&api_url, &token, &query.unwrap_or_default(), &state.storage).await
+}
 
-        (config.api_url.clone(), config.access_token.clone().unwrap())
+/// Ask where to save, then export the history or the summary report
+#[tauri::command]
+async fn export_history(
+    state: tauri::State<'_, AppState>,
+    kind: export::ExportKind,
+    format: export::ExportFormat,
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+) -> Result<Option<serde_json::Value>, String> {
+    let name = match kind {
+        export::ExportKind::History => "history",
+        export::ExportKind::Summary => "summary",
     };
+    let extension = match format {
+        export::ExportFormat::Csv => "csv",
+        export::ExportFormat::Jsonl => "jsonl",
+    };
+    let today = chrono::Local::now().format("%Y-%m-%d");
+
+    let Some(path) = FileDialogBuilder::new()
+        .set_file_name(&format!("filesorter-{}-{}.{}", name, today, extension))
+        .add_filter(extension, &[extension])
+        .save_file()
+    else {
+        return Ok(None);
+    };
+
+    let watched = state.config.lock().map_err(|e| e.to_string())?.enabled_folders();
+    // Only reading needs storage; the watcher can carry on while the file is written
+    let collected = {
+        let storage = state.storage.lock().map_err(|e| e.to_string())?;
+        export::Export::collect(&storage, kind, from, to, &watched)
+    };
+    let rows = collected.write(format, &path)?;
+
+    Ok(Some(serde_json::json!({ "path": path.to_string_lossy(), "rows": rows })))
+}
+
+/// Entries from the log files, filtered by level, time, file id or text
+#[tauri::command]
+async fn get_logs(query: Option<logging::LogQuery>) -> Result<Vec<logging::LogEntry>, String> {
+    // Scanning several megabytes of logs must not stall the command thread
+    let query = query.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || logging::read(&query))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Local aggregates over the move history; works without the server
+#[tauri::command]
+fn get_statistics(
+    state: tauri::State<AppState>,
+    query: Option<statistics::StatisticsQuery>,
+) -> Result<statistics::Statistics, String> {
+    let watched = state.config.lock().map_err(|e| e.to_string())?.enabled_folders();
+    let storage = state.storage.lock().map_err(|e| e.to_string())?;
+    Ok(statistics::compute(&storage, &query.unwrap_or_default(), &watched))
+}
+
+#[tauri::command]
+fn get_paused_queue(app: tauri::AppHandle) -> Vec<QueuedFile> {
+    pause::queued_files(&app)
+}
+
+/// Sort files held back by a pause; `paths` limits it to a selection
+#[tauri::command]
+fn sort_paused_files(app: tauri::AppHandle, paths: Option<Vec<String>>) -> Result<usize, String> {
+    pause::sort_queued(&app, paths.as_deref())
+}
+
+#[tauri::command]
+fn discard_paused_files(app: tauri::AppHandle, paths: Option<Vec<String>>) -> usize {
+    pause::discard_queued(&app, paths.as_deref())
+}
+
+/// Pause for a preset length; the watcher resumes on its own afterwards
+#[tauri::command]
+fn pause_for(app: tauri::AppHandle, duration: pause::PauseDuration) -> serde_json::Value {
+    pause::pause_for(&app, duration);
+    status_json(&app.state::<AppState>())
+}
+
+// ============================================
+// API Proxy Commands
+// ============================================
+
+/// API URL and access token of the active profile
+fn api_credentials(state: &AppState) -> Result<(String, String), String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    let token = config
+        .access_token
+        .clone()
+        .ok_or_else(|| "Not logged in".to_string())?;
+    Ok((config.api_url.clone(), token))
+}
+
+#[tauri::command]
+async fn get_user_stats(state: tauri::State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let (api_url, token) = api_credentials(&state)?;
+    api_client::get_user_stats(&api_url, &token).await
+}
+
+#[tauri::command]
+async fn extract_content(
+    state: tauri::State<'_, AppState>,
+    content_base64: String,
+    extension: String,
+    filename: String,
+) -> Result<serde_json::Value, String> {
+    let (api_url, token) = api_credentials(&state)?;
+    let request = serde_json::json!({
+        "content_base64": content_base64,
+        "extension": extension,
+        "filename": filename,
+    });
+    api_client::extract_content(&api_url, &token, &request).await
+}
+
+#[tauri::command]
+async fn analyze_organize(
+    state: tauri::State<'_, AppState>,
+    request: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let (api_url, token) = api_credentials(&state)?;
+    api_client::analyze_organize(&api_url, &token, &request).await
+}
+
+#[tauri::command]
+async fn generate_rules(
+    state: tauri::State<'_, AppState>,
+    request: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let (api_url, token) = api_credentials(&state)?;
+    api_client::generate_rules(&api_url, &token, &request).await
+}
 
-    api_client::get_recent_actions(&api_url, &token).await
+/// Record a move made by auto-organize in the server history
+#[tauri::command]
+async fn log_organize_action(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+    source_path: String,
+    dest_path: String,
+    confidence: f64,
+) -> Result<(), String> {
+    let (api_url, token) = api_credentials(&state)?;
+    let request = ActionLogRequest {
+        filename,
+        source_path,
+        dest_path,
+        category_id: None,
+        rule_id: None,
+        confidence,
+    };
+    api_client::log_action(&api_url, &token, &request).await?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -279,28 +740,25 @@ async fn start_watching(
     app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
 ) -> Result<(), String> {
-    let (folders, api_url, token) = {
+    spawn_watcher(&app, &state).await
+}
+
+async fn spawn_watcher(app: &tauri::AppHandle, state: &AppState) -> Result<(), String> {
+    let settings = {
         let config = state.config.lock().map_err(|e| e.to_string())?;
-        let folders = config.watched_folders.clone();
-        let api_url = config.api_url.clone();
-        let token = config.access_token.clone();
-        (folders, api_url, token)
+        WatcherSettings::from_config(&config)
     };
 
-    if token.is_none() {
+    let Some(settings) = settings else {
         return Err("Not logged in".to_string());
-    }
-
-    let is_paused = state.is_paused.clone();
-    let files_today = state.files_today.clone();
+    };
 
     let watcher = FileWatcher::new(
-        folders,
-        api_url,
-        token.unwrap(),
-        app.clone(),
-        is_paused,
-        files_today,
+        settings,
+        Some(app.clone()),
+        state.is_paused.clone(),
+        state.storage.clone(),
+        state.duplicate_index.clone(),
     );
 
     watcher.start().await.map_err(|e| e.to_string())?;
@@ -309,14 +767,100 @@ async fn start_watching(
         let mut watcher_guard = state.watcher.lock().map_err(|e| e.to_string())?;
         *watcher_guard = Some(watcher);
     }
+    events::watcher_state_changed(app);
+
+    // Files left queued by a pause that ended while nothing was watching
+    if !pause::is_paused(app) {
+        pause::release_queue(app);
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-fn stop_watching(state: tauri::State<AppState>) -> Result<(), String> {
-    let mut watcher = state.watcher.lock().map_err(|e| e.to_string())?;
-    *watcher = None;
+fn stop_watching(app: tauri::AppHandle, state: tauri::State<AppState>) -> Result<(), String> {
+    *state.watcher.lock().map_err(|e| e.to_string())? = None;
+    events::watcher_state_changed(&app);
+    Ok(())
+}
+
+// ============================================
+// Profile Commands
+// ============================================
+
+#[derive(Debug, Serialize)]
+struct ProfileInfo {
+    name: String,
+    is_active: bool,
+    api_url: String,
+    watched_folders: Vec<String>,
+    is_logged_in: bool,
+}
+
+#[tauri::command]
+fn list_profiles(state: tauri::State<AppState>) -> Result<Vec<ProfileInfo>, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+
+    Ok(config
+        .profile_names()
+        .into_iter()
+        .filter_map(|name| {
+            let profile = config.profile(&name)?;
+            Some(ProfileInfo {
+                is_active: name == config.active_profile,
+                api_url: profile.api_url,
+                watched_folders: profile.watched_folders.into_iter().map(|f| f.path).collect(),
+                is_logged_in: profile.access_token.is_some(),
+                name,
+            })
+        })
+        .collect())
+}
+
+/// Create a profile, optionally as a copy of `clone_from` including its rules
+#[tauri::command]
+fn create_profile(
+    state: tauri::State<AppState>,
+    name: String,
+    clone_from: Option<String>,
+) -> Result<(), String> {
+    let updated = AppConfig::update_stored(|config| {
+        config.create_profile(&name, clone_from.as_deref())
+    })
+    .map_err(|e| e.to_string())?;
+
+    if let Some(source) = &clone_from {
+        let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+        let rules = storage.cached_rules(source);
+        storage.cache_rules(name.trim(), rules);
+    }
+
+    *state.config.lock().map_err(|e| e.to_string())? = updated;
+    Ok(())
+}
+
+/// Activate another profile and restart the watcher with its settings
+#[tauri::command]
+async fn switch_profile(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    name: String,
+) -> Result<(), String> {
+    let updated = AppConfig::update_stored(|config| config.switch_profile(&name))
+        .map_err(|e| e.to_string())?;
+    let is_logged_in = updated.access_token.is_some();
+
+    *state.config.lock().map_err(|e| e.to_string())? = updated;
+    *state.watcher.lock().map_err(|e| e.to_string())? = None;
+
+    if is_logged_in {
+        spawn_watcher(&app, &state).await?;
+    } else {
+        events::watcher_state_changed(&app);
+    }
+
+    log::info!("Switched to profile {}", name);
+    refresh_tray_menu(&app);
     Ok(())
 }
 
@@ -324,6 +868,11 @@ fn stop_watching(state: tauri::State<AppState>) -> Result<(), String> {
 // Auto-Organize Commands
 // ============================================
 
+fn path_policy(state: &AppState) -> Result<PathPolicy, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(PathPolicy::from_config(&config))
+}
+
 #[derive(Debug, Serialize)]
 struct ExistingFolderInfo {
     folder_name: String,
@@ -334,16 +883,15 @@ struct ExistingFolderInfo {
 
 /// Scan for existing subfolders and their contents
 #[tauri::command]
-async fn scan_existing_folders(folder_path: String) -> Result<Vec<ExistingFolderInfo>, String> {
-    let path = Path::new(&folder_path);
-    
-    if !path.exists() {
-        return Err(format!("Folder does not exist: {}", folder_path));
-    }
+async fn scan_existing_folders(
+    state: tauri::State<'_, AppState>,
+    folder_path: String,
+) -> Result<Vec<ExistingFolderInfo>, String> {
+    let path = path_policy(&state)?.check(Path::new(&folder_path))?;
     
     let mut folders = Vec::new();
     
-    let entries = fs::read_dir(path).map_err(|e| e.to_string())?;
+    let entries = fs::read_dir(&path).map_err(|e| e.to_string())?;
     
     for entry in entries {
         let entry = entry.map_err(|e| e.to_string())?;
@@ -368,15 +916,13 @@ async fn scan_existing_folders(folder_path: String) -> Result<Vec<ExistingFolder
         let mut file_count = 0;
         
         if let Ok(sub_entries) = fs::read_dir(&entry_path) {
-            for sub_entry in sub_entries {
-                if let Ok(sub_entry) = sub_entry {
-                    let sub_path = sub_entry.path();
-                    if sub_path.is_file() {
-                        file_count += 1;
-                        if sample_files.len() < 10 {
-                            if let Some(name) = sub_path.file_name().and_then(|n| n.to_str()) {
-                                sample_files.push(name.to_string());
-                            }
+            for sub_entry in sub_entries.flatten() {
+                let sub_path = sub_entry.path();
+                if sub_path.is_file() {
+                    file_count += 1;
+                    if sample_files.len() < 10 {
+                        if let Some(name) = sub_path.file_name().and_then(|n| n.to_str()) {
+                            sample_files.push(name.to_string());
                         }
                     }
                 }
@@ -399,12 +945,11 @@ async fn scan_existing_folders(folder_path: String) -> Result<Vec<ExistingFolder
 
 /// Scan a folder and return list of files with metadata
 #[tauri::command]
-async fn scan_folder_for_organize(folder_path: String) -> Result<Vec<FileInfoForOrganize>, String> {
-    let path = Path::new(&folder_path);
-    
-    if !path.exists() {
-        return Err(format!("Folder does not exist: {}", folder_path));
-    }
+async fn scan_folder_for_organize(
+    state: tauri::State<'_, AppState>,
+    folder_path: String,
+) -> Result<Vec<FileInfoForOrganize>, String> {
+    let path = path_policy(&state)?.check(Path::new(&folder_path))?;
     
     if !path.is_dir() {
         return Err(format!("Path is not a directory: {}", folder_path));
@@ -413,7 +958,7 @@ async fn scan_folder_for_organize(folder_path: String) -> Result<Vec<FileInfoFor
     let mut files = Vec::new();
     
     // Read directory entries
-    let entries = fs::read_dir(path).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let entries = fs::read_dir(&path).map_err(|e| format!("Failed to read directory: {}", e))?;
     
     for entry in entries {
         let entry = match entry {
@@ -451,42 +996,114 @@ async fn scan_folder_for_organize(folder_path: String) -> Result<Vec<FileInfoFor
                 datetime.to_rfc3339()
             });
         
+        let duplicate_of = duplicates::find_duplicate_in(&state.duplicate_index, &file_path)
+            .await
+            .map(|p| p.to_string_lossy().to_string());
+
         files.push(FileInfoForOrganize {
             filename,
             extension,
             size_bytes,
             path: file_path.to_string_lossy().to_string(),
             modified,
+            duplicate_of,
         });
     }
-    
+
+    // Catch copies within the scanned folder itself, off the async runtime
+    let paths: Vec<PathBuf> = files.iter().map(|file| PathBuf::from(&file.path)).collect();
+    let within = tauri::async_runtime::spawn_blocking(move || {
+        let mut scanned = DuplicateIndex::default();
+        paths
+            .iter()
+            .map(|path| {
+                let duplicate = scanned.find_duplicate(path);
+                scanned.insert(path);
+                duplicate
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for (file, duplicate) in files.iter_mut().zip(within) {
+        if file.duplicate_of.is_none() {
+            file.duplicate_of = duplicate.map(|p| p.to_string_lossy().to_string());
+        }
+    }
+
     Ok(files)
 }
 
-/// Read file content (first N bytes) for content extraction
+/// Upper bound for a single read, regardless of what the UI asks for
+const MAX_READ_BYTES: usize = 16 * 1024 * 1024;
+
+/// Read a bounded range of a file for content extraction.
+///
+/// Only `max_bytes` starting at `offset` are read from disk. With
+/// `tail_bytes` the end of the file is sampled as well, which helps formats
+/// that keep their metadata at the end.
 #[tauri::command]
-async fn read_file_content(file_path: String, max_bytes: Option<usize>) -> Result<Vec<u8>, String> {
-    let path = Path::new(&file_path);
+async fn read_file_content(
+    state: tauri::State<'_, AppState>,
+    file_path: String,
+    offset: Option<u64>,
+    max_bytes: Option<usize>,
+    tail_bytes: Option<usize>,
+) -> Result<FileContent, String> {
+    let path = path_policy(&state)?.check(Path::new(&file_path))?;
     
-    if !path.exists() {
-        return Err(format!("File does not exist: {}", file_path));
-    }
+    let offset = offset.unwrap_or(0);
+    let max_bytes = max_bytes.unwrap_or(1024 * 1024).min(MAX_READ_BYTES); // Default 1MB
     
-    let max_bytes = max_bytes.unwrap_or(1024 * 1024); // Default 1MB
+    let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let total_size = file.metadata().map_err(|e| e.to_string())?.len();
     
-    let content = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let head = read_range(&mut file, offset, max_bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
     
-    // Limit content size
-    if content.len() > max_bytes {
-        Ok(content[..max_bytes].to_vec())
-    } else {
-        Ok(content)
-    }
+    // Sample the tail without overlapping the head range
+    let (tail, tail_offset) = match tail_bytes {
+        Some(tail_bytes) if tail_bytes > 0 => {
+            let head_end = offset + head.len() as u64;
+            let tail_bytes = tail_bytes.min(MAX_READ_BYTES) as u64;
+            let tail_start = total_size.saturating_sub(tail_bytes).max(head_end);
+            
+            if tail_start < total_size {
+                let tail = read_range(&mut file, tail_start, (total_size - tail_start) as usize)
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                (Some(BASE64.encode(tail)), Some(tail_start))
+            } else {
+                (None, None)
+            }
+        }
+        _ => (None, None),
+    };
+    
+    Ok(FileContent {
+        data: BASE64.encode(head),
+        offset,
+        total_size,
+        tail,
+        tail_offset,
+    })
+}
+
+fn read_range(file: &mut fs::File, offset: u64, length: usize) -> std::io::Result<Vec<u8>> {
+    let available = file.metadata()?.len().saturating_sub(offset);
+    let length = length.min(usize::try_from(available).unwrap_or(usize::MAX));
+    file.seek(SeekFrom::Start(offset))?;
+    
+    let mut buffer = Vec::with_capacity(length);
+    file.by_ref().take(length as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
 }
 
 /// Execute file moves for auto-organize
 #[tauri::command]
 async fn execute_file_moves(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
     base_folder: String,
     moves: Vec<MoveAction>,
     create_folders: bool,
@@ -495,12 +1112,46 @@ async fn execute_file_moves(
     let mut skipped_count = 0u32;
     let mut errors = Vec::new();
     
-    let base_path = Path::new(&base_folder);
+    let policy = path_policy(&state)?;
+    let base_path = policy.check(Path::new(&base_folder))?;
+    let mut moved_files = Vec::new();
     
     for action in moves {
-        let source = Path::new(&action.source_path);
-        let dest_folder = base_path.join(&action.dest_folder);
-        let dest_file = dest_folder.join(&action.filename);
+        let source = match policy.check(Path::new(&action.source_path)) {
+            Ok(source) if source.is_file() => source,
+            Ok(_) => {
+                errors.push(format!("Not a file: {}", action.source_path));
+                skipped_count += 1;
+                continue;
+            }
+            Err(e) => {
+                errors.push(e);
+                skipped_count += 1;
+                continue;
+            }
+        };
+        let dest_folder = match path_guard::resolve_destination(&base_path, &action.dest_folder) {
+            Ok(folder) => folder,
+            Err(e) => {
+                errors.push(e);
+                skipped_count += 1;
+                continue;
+            }
+        };
+        let filename = match path_guard::sanitize_file_name(&action.filename) {
+            Ok(name) => name,
+            Err(e) => {
+                errors.push(e);
+                skipped_count += 1;
+                continue;
+            }
+        };
+        let dest_file = dest_folder.join(&filename);
+        if let Err(e) = policy.check_destination(&dest_file) {
+            errors.push(e);
+            skipped_count += 1;
+            continue;
+        }
         
         // Create destination folder if needed
         if create_folders && !dest_folder.exists() {
@@ -517,25 +1168,60 @@ async fn execute_file_moves(
             continue;
         }
         
+        let size_bytes = fs::metadata(&source).map(|m| m.len()).unwrap_or(0);
+        
         // Move the file
-        match fs::rename(source, &dest_file) {
-            Ok(_) => {
-                moved_count += 1;
-            }
+        let moved = match fs::rename(&source, &dest_file) {
+            Ok(_) => true,
             Err(e) => {
                 // Try copy + delete if rename fails (cross-filesystem)
-                match fs::copy(source, &dest_file) {
+                match fs::copy(&source, &dest_file) {
                     Ok(_) => {
-                        let _ = fs::remove_file(source);
-                        moved_count += 1;
+                        let _ = fs::remove_file(&source);
+                        true
                     }
                     Err(copy_err) => {
                         errors.push(format!("Failed to move {}: {} / {}", action.filename, e, copy_err));
                         skipped_count += 1;
+                        false
                     }
                 }
             }
+        };
+        
+        if moved {
+            moved_count += 1;
+            moved_files.push(MoveRecord {
+                id: 0,
+                filename,
+                source_path: source.to_string_lossy().to_string(),
+                dest_path: dest_file.to_string_lossy().to_string(),
+                category: Some(action.dest_folder.clone()),
+                rule_id: None,
+                rule_name: None,
+                confidence: None,
+                method: "auto_organize".to_string(),
+                size_bytes,
+                timestamp: chrono::Utc::now().timestamp(),
+                undone: false,
+            });
+        }
+    }
+    
+    if let Ok(mut index) = state.duplicate_index.lock() {
+        for record in &moved_files {
+            index.insert(Path::new(&record.dest_path));
         }
+        index.save().ok();
+    }
+    
+    if let Ok(mut storage) = state.storage.lock() {
+        for record in &moved_files {
+            storage.record_move(record);
+        }
+    }
+    if !moved_files.is_empty() {
+        refresh_tray_menu(&app);
     }
     
     Ok(MoveResult {
@@ -562,3 +1248,58 @@ fn get_user_folders() -> serde_json::Value {
     })
 }
 
+/// Let the user pick a folder in a native dialog and approve it as a root
+#[tauri::command]
+async fn pick_folder(
+    state: tauri::State<'_, AppState>,
+    title: Option<String>,
+    default_path: Option<String>,
+) -> Result<Option<String>, String> {
+    let mut dialog = FileDialogBuilder::new();
+    if let Some(title) = title {
+        dialog = dialog.set_title(&title);
+    }
+    if let Some(default_path) = default_path {
+        dialog = dialog.set_directory(default_path);
+    }
+
+    let Some(picked) = dialog.pick_folder() else {
+        return Ok(None);
+    };
+    let picked = picked.to_string_lossy().to_string();
+
+    let mut config = state.config.lock().map_err(|e| e.to_string())?;
+    if !config.approved_roots.contains(&picked) {
+        config.approved_roots.push(picked.clone());
+        config.save().map_err(|e| e.to_string())?;
+    }
+
+    Ok(Some(picked))
+}
+
+/// Group near-duplicate pictures in the local index for review
+#[tauri::command]
+async fn find_similar_images(
+    state: tauri::State<'_, AppState>,
+    threshold: Option<u32>,
+) -> Result<Vec<similar_images::ImageCluster>, String> {
+    let index = state.duplicate_index.clone();
+    let threshold = threshold.unwrap_or(similar_images::DEFAULT_THRESHOLD);
+
+    // Decoding images is slow, keep it off the async workers and out of the
+    // index lock so the watcher can keep checking duplicates meanwhile
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut pictures = {
+            let index = index.lock().map_err(|e| e.to_string())?;
+            similar_images::pictures(&index, &LocalClassifier::new())
+        };
+        let clusters = similar_images::find_clusters(&mut pictures, threshold);
+
+        let mut index = index.lock().map_err(|e| e.to_string())?;
+        similar_images::remember_hashes(&mut index, &pictures);
+        index.save().map_err(|e| e.to_string())?;
+        Ok(clusters)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
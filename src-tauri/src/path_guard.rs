@@ -0,0 +1,331 @@
+//! Validation of destination paths coming from the API or the UI.
+//!
+//! Destinations are always treated as relative to an allowed root. Anything
+//! that would resolve outside that root is rejected and logged under the
+//! `security` log target.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::AppConfig;
+
+/// Characters that are invalid in file names on Windows or macOS.
+const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names reserved by Windows regardless of extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Resolve `destination` beneath `root`, returning the joined path.
+///
+/// Separators are normalized, every component is sanitized, and the result
+/// must stay inside `root` both lexically and after symlinks are resolved.
+pub fn resolve_destination(root: &Path, destination: &str) -> Result<PathBuf, String> {
+    let relative = match normalize_relative(destination) {
+        Ok(relative) => relative,
+        Err(reason) => return Err(reject(root, destination, &reason)),
+    };
+
+    let joined = root.join(&relative);
+
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+
+    // Components may already exist as symlinks pointing elsewhere, so check
+    // the deepest existing ancestor after resolving it.
+    let existing = joined
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or(root);
+    let canonical_existing = existing
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", existing.display(), e))?;
+
+    if !canonical_existing.starts_with(&canonical_root) {
+        return Err(reject(root, destination, "resolves outside the allowed root"));
+    }
+
+    Ok(joined)
+}
+
+/// Sanitize a single file name, rejecting anything that is not one component.
+pub fn sanitize_file_name(name: &str) -> Result<String, String> {
+    if name.contains('/') || name.contains('\\') {
+        log::warn!(target: "security", "Rejected file name {:?}: contains a path separator", name);
+        return Err(format!("Invalid file name: {}", name));
+    }
+
+    let sanitized = sanitize_component(name);
+    if sanitized.is_empty() {
+        log::warn!(target: "security", "Rejected file name {:?}: empty after sanitizing", name);
+        return Err(format!("Invalid file name: {}", name));
+    }
+
+    Ok(sanitized)
+}
+
+/// Replace characters that are invalid on any supported filesystem.
+pub fn sanitize_component(component: &str) -> String {
+    let mut cleaned: String = component
+        .chars()
+        .map(|c| if c.is_control() || INVALID_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+
+    // Windows silently drops trailing dots and spaces
+    let kept = cleaned.trim_end_matches(['.', ' ']).len();
+    cleaned.truncate(kept);
+
+    let stem = cleaned.split('.').next().unwrap_or("").to_ascii_uppercase();
+    if RESERVED_NAMES.contains(&stem.as_str()) {
+        cleaned.insert(0, '_');
+    }
+
+    cleaned
+}
+
+/// Turn a destination string into a clean relative path without `..`.
+fn normalize_relative(destination: &str) -> Result<PathBuf, String> {
+    let unified = destination.replace('\\', "/");
+
+    if unified.starts_with('/') || has_drive_prefix(&unified) {
+        return Err("absolute paths are not allowed".to_string());
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+
+    for raw in unified.split('/') {
+        let trimmed = raw.trim();
+        match trimmed {
+            "" | "." => continue,
+            ".." => {
+                if parts.pop().is_none() {
+                    return Err("path escapes the allowed root".to_string());
+                }
+            }
+            _ => {
+                let part = sanitize_component(trimmed);
+                if part.is_empty() {
+                    continue;
+                }
+                parts.push(part);
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        return Err("destination is empty".to_string());
+    }
+
+    let relative: PathBuf = parts.iter().collect();
+
+    // Belt and braces: the platform parser must agree the path is plain
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err("path contains non-normal components".to_string());
+    }
+
+    Ok(relative)
+}
+
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn reject(root: &Path, destination: &str, reason: &str) -> String {
+    log::warn!(
+        target: "security",
+        "Rejected destination {:?} under {}: {}",
+        destination,
+        root.display(),
+        reason
+    );
+    format!("Rejected destination {}: {}", destination, reason)
+}
+
+/// Filesystem roots the webview is allowed to touch through commands.
+///
+/// A path passes when it lives under a watched folder or a folder the user
+/// picked in a native dialog, and is not inside an always-denied location.
+#[derive(Debug, Clone)]
+pub struct PathPolicy {
+    roots: Vec<PathBuf>,
+    denied: Vec<PathBuf>,
+}
+
+impl PathPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let roots = config
+            .watched_folders
+            .iter()
+            .map(|f| &f.path)
+            .chain(config.approved_roots.iter())
+            .filter_map(|root| Path::new(root).canonicalize().ok())
+            .collect();
+
+        Self {
+            roots,
+            denied: denied_locations(),
+        }
+    }
+
+    /// Also allow everything under `root`, for folders named on the command line.
+    pub fn with_root(mut self, root: &Path) -> Self {
+        if let Ok(root) = root.canonicalize() {
+            self.roots.push(root);
+        }
+        self
+    }
+
+    /// Check `path` against the policy and return its canonical form.
+    pub fn check(&self, path: &Path) -> Result<PathBuf, String> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|_| format!("Path does not exist: {}", path.display()))?;
+        self.allow(path, canonical)
+    }
+
+    /// Check a move target that may not exist yet.
+    ///
+    /// The deepest existing ancestor is resolved and the missing components
+    /// are appended, so symlinked parents are judged by where they point.
+    pub fn check_destination(&self, path: &Path) -> Result<PathBuf, String> {
+        let existing = path
+            .ancestors()
+            .find(|p| p.exists())
+            .ok_or_else(|| format!("Path does not exist: {}", path.display()))?;
+        let missing = path.strip_prefix(existing).unwrap_or(Path::new(""));
+        let canonical = existing
+            .canonicalize()
+            .map_err(|_| format!("Path does not exist: {}", path.display()))?
+            .join(missing);
+        self.allow(path, canonical)
+    }
+
+    fn allow(&self, path: &Path, canonical: PathBuf) -> Result<PathBuf, String> {
+        if self.denied.iter().any(|denied| canonical.starts_with(denied)) {
+            log::warn!(target: "security", "Denied access to sensitive path {}", canonical.display());
+            return Err(format!("Access denied: {}", path.display()));
+        }
+
+        if !self.roots.iter().any(|root| canonical.starts_with(root)) {
+            log::warn!(target: "security", "Denied access outside approved roots: {}", canonical.display());
+            return Err(format!("Access denied: {}", path.display()));
+        }
+
+        Ok(canonical)
+    }
+}
+
+/// Locations that commands must never read from or move files out of.
+fn denied_locations() -> Vec<PathBuf> {
+    let mut denied = Vec::new();
+
+    if let Some(home) = dirs::home_dir() {
+        for dir in [".ssh", ".gnupg", ".aws", ".kube"] {
+            denied.push(home.join(dir));
+        }
+    }
+
+    denied.push(AppConfig::config_dir());
+
+    // Compare against canonical paths so symlinked homes still match
+    denied
+        .into_iter()
+        .map(|p| p.canonicalize().unwrap_or(p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("filesorter-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn resolves_relative_destinations_under_root() {
+        let root = temp_root("resolve");
+
+        assert_eq!(resolve_destination(&root, "Docs/Invoices").unwrap(), root.join("Docs").join("Invoices"));
+        assert_eq!(resolve_destination(&root, "Docs\\./Invoices/").unwrap(), root.join("Docs").join("Invoices"));
+        assert_eq!(resolve_destination(&root, "Docs/../Pictures").unwrap(), root.join("Pictures"));
+        assert_eq!(resolve_destination(&root, " a:b / c? ").unwrap(), root.join("a_b").join("c_"));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn rejects_destinations_escaping_root() {
+        let root = temp_root("escape");
+
+        for destination in ["../outside", "a/../../outside", "/etc", "\\\\server\\share", "C:\\Windows", "c:/x", "", "./.", "..."] {
+            assert!(resolve_destination(&root, destination).is_err(), "{:?} was accepted", destination);
+        }
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leading_outside_root() {
+        let root = temp_root("symlink");
+        let outside = temp_root("symlink-target");
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        assert!(resolve_destination(&root, "link").is_err());
+        assert!(resolve_destination(&root, "link/nested").is_err());
+
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::remove_dir_all(&outside).ok();
+    }
+
+    #[test]
+    fn destinations_must_avoid_denied_locations() {
+        let root = temp_root("policy");
+        std::fs::create_dir_all(root.join(".ssh")).unwrap();
+        let root = root.canonicalize().unwrap();
+        let policy = PathPolicy {
+            roots: vec![root.clone()],
+            denied: vec![root.join(".ssh")],
+        };
+
+        assert_eq!(
+            policy.check_destination(&root.join("Docs").join("a.txt")).unwrap(),
+            root.join("Docs").join("a.txt")
+        );
+        assert!(policy.check_destination(&root.join(".ssh").join("a.txt")).is_err());
+        assert!(policy.check_destination(&root.join(".ssh").join("new").join("a.txt")).is_err());
+        assert!(policy.check(&root.join(".ssh")).is_err());
+        assert!(policy.check_destination(&std::env::temp_dir().join("elsewhere.txt")).is_err());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn sanitizes_components() {
+        assert_eq!(sanitize_component("report<1>?.pdf"), "report_1__.pdf");
+        assert_eq!(sanitize_component("tab\there"), "tab_here");
+        assert_eq!(sanitize_component("trailing. . "), "trailing");
+        assert_eq!(sanitize_component("CON"), "_CON");
+        assert_eq!(sanitize_component("lpt1.txt"), "_lpt1.txt");
+        assert_eq!(sanitize_component("CONSOLE.txt"), "CONSOLE.txt");
+    }
+
+    #[test]
+    fn file_names_must_be_single_components() {
+        assert_eq!(sanitize_file_name("photo.jpg").unwrap(), "photo.jpg");
+        assert_eq!(sanitize_file_name("a|b.txt").unwrap(), "a_b.txt");
+        assert!(sanitize_file_name("dir/photo.jpg").is_err());
+        assert!(sanitize_file_name("dir\\photo.jpg").is_err());
+        assert!(sanitize_file_name("..").is_err());
+        assert!(sanitize_file_name(". .").is_err());
+    }
+}
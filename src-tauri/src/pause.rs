@@ -0,0 +1,260 @@
+//! Pausing and resuming the watcher, optionally for a limited time.
+//!
+//! The pause is kept in local storage so a restart does not silently resume
+//! the watcher, and a timed pause still ends on schedule afterwards.
+//!
+//! Files that arrive during a pause are held in a queue in local storage.
+//! On resume they are sorted, or, with `review_paused_files` set, left for
+//! the user to sort or discard from the UI. Queued files whose folder is no
+//! longer watched are dropped when released.
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::statistics;
+use crate::storage::QueuedFile;
+use crate::AppState;
+
+/// Storage key holding the pause: `"indefinite"` or an RFC 3339 deadline
+const STATE_KEY: &str = "pause";
+const INDEFINITE: &str = "indefinite";
+
+/// How often the tray countdown is refreshed during a timed pause
+const TRAY_REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Pause lengths offered in the tray and the UI
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseDuration {
+    FifteenMinutes,
+    OneHour,
+    UntilTomorrow,
+    Indefinitely,
+}
+
+impl PauseDuration {
+    /// When a pause of this length started now would end
+    pub fn deadline(self) -> Option<DateTime<Local>> {
+        let now = Local::now();
+        match self {
+            Self::FifteenMinutes => Some(now + Duration::minutes(15)),
+            Self::OneHour => Some(now + Duration::hours(1)),
+            Self::UntilTomorrow => statistics::start_of_day(now.date_naive() + Duration::days(1)),
+            Self::Indefinitely => None,
+        }
+    }
+}
+
+/// Pause or resume the watcher. A deadline resumes it automatically.
+pub fn set(app: &AppHandle, paused: bool, until: Option<DateTime<Local>>) {
+    let state = app.state::<AppState>();
+    let until = if paused { until } else { None };
+
+    let was_paused = match state.is_paused.lock() {
+        Ok(mut is_paused) => std::mem::replace(&mut *is_paused, paused),
+        Err(_) => false,
+    };
+    if let Ok(mut paused_until) = state.paused_until.lock() {
+        *paused_until = until;
+    }
+
+    let stored = match (paused, until) {
+        (false, _) => None,
+        (true, None) => Some(INDEFINITE.to_string()),
+        (true, Some(deadline)) => Some(deadline.to_rfc3339()),
+    };
+    if let Ok(mut storage) = state.storage.lock() {
+        storage.set_agent_state(STATE_KEY, stored.as_deref());
+    }
+
+    if let Some(deadline) = until {
+        schedule_resume(app.clone(), deadline);
+    }
+
+    crate::refresh_tray_menu(app);
+    events::watcher_state_changed(app);
+
+    if was_paused && !paused {
+        release_queue(app);
+    }
+}
+
+/// Pause for one of the preset lengths
+pub fn pause_for(app: &AppHandle, duration: PauseDuration) {
+    set(app, true, duration.deadline());
+}
+
+/// Flip between paused and running, dropping any deadline
+pub fn toggle(app: &AppHandle) -> bool {
+    let paused = !is_paused(app);
+    set(app, paused, None);
+    paused
+}
+
+pub fn is_paused(app: &AppHandle) -> bool {
+    app.state::<AppState>()
+        .is_paused
+        .lock()
+        .map(|p| *p)
+        .unwrap_or(false)
+}
+
+/// Bring back a pause saved before the last shutdown
+pub fn restore(app: &AppHandle) {
+    let stored = app
+        .state::<AppState>()
+        .storage
+        .lock()
+        .ok()
+        .and_then(|storage| storage.agent_state(STATE_KEY));
+
+    match stored.as_deref() {
+        None => {}
+        Some(INDEFINITE) => set(app, true, None),
+        Some(value) => match DateTime::parse_from_rfc3339(value) {
+            Ok(deadline) if deadline > Local::now() => {
+                set(app, true, Some(deadline.with_timezone(&Local)))
+            }
+            Ok(_) => {
+                log::info!("Timed pause ended while the agent was stopped, resuming");
+                set(app, false, None);
+            }
+            Err(e) => {
+                log::warn!("Ignoring unreadable saved pause {:?}: {}", value, e);
+                set(app, false, None);
+            }
+        },
+    }
+}
+
+pub fn queued_files(app: &AppHandle) -> Vec<QueuedFile> {
+    app.state::<AppState>()
+        .storage
+        .lock()
+        .map(|storage| storage.paused_queue())
+        .unwrap_or_default()
+}
+
+/// Sort what queued up during the pause, unless the user wants to review it
+pub fn release_queue(app: &AppHandle) {
+    let count = queued_files(app).len();
+    if count == 0 {
+        return;
+    }
+
+    let review = app
+        .state::<AppState>()
+        .config
+        .lock()
+        .map(|c| c.review_paused_files)
+        .unwrap_or(false);
+
+    if review {
+        let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+            .title("FileSorter")
+            .body(format!("{} файлов, пришедших во время паузы, ждут проверки", count))
+            .show();
+        return;
+    }
+
+    if let Err(e) = sort_queued(app, None) {
+        log::warn!("Paused files stay queued: {}", e);
+    }
+}
+
+/// Sort queued files, all of them or only `paths`; returns how many were sent
+pub fn sort_queued(app: &AppHandle, paths: Option<&[String]>) -> Result<usize, String> {
+    if is_paused(app) {
+        return Err("Watcher is paused".to_string());
+    }
+
+    let state = app.state::<AppState>();
+    let watcher = state.watcher.lock().map_err(|e| e.to_string())?;
+    let watcher = watcher.as_ref().ok_or("Watcher is not running")?;
+
+    let selected: Vec<PathBuf> = {
+        let mut storage = state.storage.lock().map_err(|e| e.to_string())?;
+        let selected: Vec<PathBuf> = storage
+            .paused_queue()
+            .into_iter()
+            .filter(|file| paths.is_none_or(|paths| paths.contains(&file.path)))
+            .map(|file| PathBuf::from(file.path))
+            .collect();
+
+        // Released files leave the queue before they reach the watcher, which
+        // otherwise keeps queued files back for review
+        for path in &selected {
+            storage.unqueue_paused(&path.to_string_lossy());
+        }
+        selected
+    };
+    if !selected.is_empty() {
+        events::queue_changed(app);
+    }
+
+    let count = watcher.requeue(selected)?;
+    log::info!("Released {} paused files for sorting", count);
+    Ok(count)
+}
+
+/// Drop queued files without sorting them; returns how many were removed
+pub fn discard_queued(app: &AppHandle, paths: Option<&[String]>) -> usize {
+    let state = app.state::<AppState>();
+    let Ok(mut storage) = state.storage.lock() else {
+        return 0;
+    };
+
+    let mut count = 0;
+    for file in storage.paused_queue() {
+        if paths.is_none_or(|paths| paths.contains(&file.path)) && storage.unqueue_paused(&file.path) {
+            count += 1;
+        }
+    }
+    drop(storage);
+
+    if count > 0 {
+        events::queue_changed(app);
+    }
+    count
+}
+
+/// Time left as shown in the tray, e.g. "ещё 1 ч 05 мин"
+pub fn format_remaining(deadline: DateTime<Local>) -> String {
+    // Round up so the last minute does not read "ещё 0 мин"
+    let minutes = ((deadline - Local::now()).num_seconds().max(0) + 59) / 60;
+    if minutes >= 60 {
+        format!("ещё {} ч {:02} мин", minutes / 60, minutes % 60)
+    } else {
+        format!("ещё {} мин", minutes)
+    }
+}
+
+fn schedule_resume(app: AppHandle, deadline: DateTime<Local>) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let remaining = (deadline - Local::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(remaining.min(TRAY_REFRESH)).await;
+
+            // A newer pause or a manual resume replaces the deadline
+            let still_due = app
+                .state::<AppState>()
+                .paused_until
+                .lock()
+                .map(|until| *until == Some(deadline))
+                .unwrap_or(false);
+            if !still_due {
+                return;
+            }
+
+            if Local::now() >= deadline {
+                log::info!("Timed pause ended, resuming");
+                set(&app, false, None);
+                return;
+            }
+            crate::refresh_tray_menu(&app);
+        }
+    });
+}
@@ -0,0 +1,151 @@
+//! Perceptual near-duplicate detection for images.
+//!
+//! Each picture gets an average hash (aHash) and a difference hash (dHash).
+//! Two images count as near-duplicates when both hashes are within a small
+//! Hamming distance, which survives re-encoding and resizing.
+
+use image::imageops::FilterType;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::classifier::LocalClassifier;
+use crate::duplicates::{DuplicateIndex, IndexedFile, PerceptualHash};
+
+/// Default maximum Hamming distance (out of 64 bits) for a match
+pub const DEFAULT_THRESHOLD: u32 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterMember {
+    pub path: String,
+    pub size: u64,
+    /// Similarity to the cluster's first member, from 0.0 to 1.0
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageCluster {
+    pub files: Vec<ClusterMember>,
+}
+
+/// Compute both perceptual hashes for an image file.
+pub fn perceptual_hash(path: &Path) -> Option<PerceptualHash> {
+    let img = image::open(path).ok()?;
+
+    let small = img.resize_exact(8, 8, FilterType::Triangle).to_luma8();
+    let pixels: Vec<u8> = small.pixels().map(|p| p.0[0]).collect();
+    let mean = pixels.iter().map(|&p| p as u32).sum::<u32>() / pixels.len() as u32;
+    let average = pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, &p)| p as u32 >= mean)
+        .fold(0u64, |hash, (i, _)| hash | (1 << i));
+
+    let wide = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut difference = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            if wide.get_pixel(x, y).0[0] > wide.get_pixel(x + 1, y).0[0] {
+                difference |= 1 << (y * 8 + x);
+            }
+        }
+    }
+
+    Some(PerceptualHash { average, difference })
+}
+
+/// Copy the indexed pictures out, so hashing can run without the index lock.
+pub fn pictures(index: &DuplicateIndex, classifier: &LocalClassifier) -> Vec<IndexedFile> {
+    index
+        .files()
+        .filter(|file| {
+            let extension = Path::new(&file.path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| format!(".{}", e))
+                .unwrap_or_default();
+            classifier.category_for_extension(&extension) == Some("Pictures")
+        })
+        .cloned()
+        .collect()
+}
+
+/// Store hashes computed by [`find_clusters`] back into the index.
+pub fn remember_hashes(index: &mut DuplicateIndex, pictures: &[IndexedFile]) {
+    let hashes: HashMap<&str, PerceptualHash> = pictures
+        .iter()
+        .filter_map(|file| Some((file.path.as_str(), file.perceptual_hash?)))
+        .collect();
+
+    for file in index.files_mut() {
+        if file.perceptual_hash.is_none() {
+            file.perceptual_hash = hashes.get(file.path.as_str()).copied();
+        }
+    }
+}
+
+/// Group pictures into clusters of near-duplicates.
+///
+/// Missing hashes are computed on the way and kept in `pictures`.
+pub fn find_clusters(pictures: &mut [IndexedFile], threshold: u32) -> Vec<ImageCluster> {
+    let mut images: Vec<(String, u64, PerceptualHash)> = Vec::new();
+
+    for file in pictures.iter_mut() {
+        if !Path::new(&file.path).exists() {
+            continue;
+        }
+
+        if file.perceptual_hash.is_none() {
+            file.perceptual_hash = perceptual_hash(Path::new(&file.path));
+        }
+        if let Some(hash) = file.perceptual_hash {
+            images.push((file.path.clone(), file.size, hash));
+        }
+    }
+
+    // Largest files first so each cluster leads with the best copy
+    images.sort_by_key(|image| std::cmp::Reverse(image.1));
+
+    let mut assigned = vec![false; images.len()];
+    let mut clusters = Vec::new();
+
+    for i in 0..images.len() {
+        if assigned[i] {
+            continue;
+        }
+
+        let (ref path, size, hash) = images[i];
+        let mut members = vec![ClusterMember {
+            path: path.clone(),
+            size,
+            similarity: 1.0,
+        }];
+
+        for j in (i + 1)..images.len() {
+            if assigned[j] {
+                continue;
+            }
+
+            let other = &images[j];
+            let average_distance = (hash.average ^ other.2.average).count_ones();
+            let difference_distance = (hash.difference ^ other.2.difference).count_ones();
+
+            if average_distance <= threshold && difference_distance <= threshold {
+                assigned[j] = true;
+                let distance = (average_distance + difference_distance) as f64 / 2.0;
+                members.push(ClusterMember {
+                    path: other.0.clone(),
+                    size: other.1,
+                    similarity: 1.0 - distance / 64.0,
+                });
+            }
+        }
+
+        if members.len() > 1 {
+            assigned[i] = true;
+            clusters.push(ImageCluster { files: members });
+        }
+    }
+
+    clusters
+}
@@ -0,0 +1,214 @@
+//! Counters and aggregates computed from local storage, available offline.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::storage::{DailyStats, LocalStorage, MoveRecord};
+
+/// Longest history `daily_history` returns
+const MAX_HISTORY_DAYS: u32 = 366;
+
+/// How often the rollover task looks at the date
+const ROLLOVER_CHECK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Files sorted since local midnight
+pub fn files_today(storage: &LocalStorage) -> u32 {
+    storage.daily_stats(Local::now().date_naive()).sorted
+}
+
+/// Counters for the last `days` days, oldest first, ending today
+pub fn daily_history(storage: &LocalStorage, days: u32) -> Vec<DailyStats> {
+    let days = days.clamp(1, MAX_HISTORY_DAYS);
+    let today = Local::now().date_naive();
+    storage.daily_history(today - Duration::days(i64::from(days) - 1), today)
+}
+
+/// Date range for `compute`; both ends are local dates and inclusive
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatisticsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Totals for one group of moves
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    pub key: String,
+    /// Display name when the key is an id, e.g. the rule name
+    pub label: Option<String>,
+    pub files: u64,
+    pub bytes: u64,
+    /// Mean classifier confidence over moves that reported one
+    pub average_confidence: Option<f64>,
+}
+
+/// Aggregates over the local move journal, undone moves excluded
+#[derive(Debug, Clone, Serialize)]
+pub struct Statistics {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub total: Bucket,
+    /// Files per classification method, e.g. `rule` or `ai`
+    pub methods: BTreeMap<String, u64>,
+    pub by_category: Vec<Bucket>,
+    pub by_rule: Vec<Bucket>,
+    pub by_folder: Vec<Bucket>,
+    /// Keyed by local date, `YYYY-MM-DD`, oldest first
+    pub by_day: Vec<Bucket>,
+    /// Keyed by ISO week, `YYYY-Www`, oldest first
+    pub by_week: Vec<Bucket>,
+}
+
+#[derive(Default)]
+struct Tally {
+    label: Option<String>,
+    files: u64,
+    bytes: u64,
+    confidence_sum: f64,
+    confidence_count: u64,
+}
+
+impl Tally {
+    fn add(&mut self, record: &MoveRecord) {
+        self.files += 1;
+        self.bytes += record.size_bytes;
+        if let Some(confidence) = record.confidence {
+            self.confidence_sum += confidence;
+            self.confidence_count += 1;
+        }
+    }
+
+    fn into_bucket(self, key: String) -> Bucket {
+        Bucket {
+            key,
+            label: self.label,
+            files: self.files,
+            bytes: self.bytes,
+            average_confidence: (self.confidence_count > 0)
+                .then(|| self.confidence_sum / self.confidence_count as f64),
+        }
+    }
+}
+
+/// Buckets in key order
+fn chronological(tallies: BTreeMap<String, Tally>) -> Vec<Bucket> {
+    tallies
+        .into_iter()
+        .map(|(key, tally)| tally.into_bucket(key))
+        .collect()
+}
+
+/// Buckets with the most files first
+fn largest_first(tallies: BTreeMap<String, Tally>) -> Vec<Bucket> {
+    let mut buckets = chronological(tallies);
+    buckets.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.key.cmp(&b.key)));
+    buckets
+}
+
+/// First moment of a local date
+///
+/// Where a DST change skips midnight the day starts at the first local time
+/// that exists.
+pub fn start_of_day(day: NaiveDate) -> Option<DateTime<Local>> {
+    let midnight = day.and_time(NaiveTime::MIN);
+    // Gaps last an hour or two; stepping by minutes covers any offset
+    (0..24 * 60)
+        .map(|minutes| midnight + Duration::minutes(minutes))
+        .find_map(|time| time.and_local_timezone(Local).earliest())
+}
+
+/// Unix time of the start of a local date
+pub fn local_midnight(day: NaiveDate) -> Option<i64> {
+    start_of_day(day).map(|t| t.timestamp())
+}
+
+/// The watched folder a file was sorted from, or its parent folder
+fn source_folder(record: &MoveRecord, watched: &[String]) -> String {
+    let source = Path::new(&record.source_path);
+    watched
+        .iter()
+        .filter(|folder| source.starts_with(folder))
+        .max_by_key(|folder| folder.len())
+        .cloned()
+        .or_else(|| source.parent().map(|p| p.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+/// Aggregate the move journal over the requested range
+pub fn compute(storage: &LocalStorage, query: &StatisticsQuery, watched: &[String]) -> Statistics {
+    let from = query.from.and_then(local_midnight);
+    let to = query.to.and_then(|day| local_midnight(day + Duration::days(1)));
+    let records = storage.moves_between(from, to);
+
+    let mut total = Tally::default();
+    let mut methods = BTreeMap::new();
+    let mut by_category: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut by_rule: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut by_folder: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut by_day: BTreeMap<String, Tally> = BTreeMap::new();
+    let mut by_week: BTreeMap<String, Tally> = BTreeMap::new();
+
+    for record in &records {
+        let Some(time) = DateTime::from_timestamp(record.timestamp, 0) else {
+            continue;
+        };
+        let date = time.with_timezone(&Local).date_naive();
+        let week = date.iso_week();
+
+        total.add(record);
+        *methods.entry(record.method.clone()).or_insert(0) += 1;
+
+        let category = record.category.clone().unwrap_or_default();
+        by_category.entry(category).or_default().add(record);
+
+        // Moves not made by a rule share the empty key
+        let rule = by_rule.entry(record.rule_id.clone().unwrap_or_default()).or_default();
+        if rule.label.is_none() {
+            rule.label = record.rule_name.clone();
+        }
+        rule.add(record);
+
+        by_folder.entry(source_folder(record, watched)).or_default().add(record);
+        by_day.entry(date.to_string()).or_default().add(record);
+        by_week
+            .entry(format!("{}-W{:02}", week.year(), week.week()))
+            .or_default()
+            .add(record);
+    }
+
+    Statistics {
+        from: query.from,
+        to: query.to,
+        total: total.into_bucket("total".to_string()),
+        methods,
+        by_category: largest_first(by_category),
+        by_rule: largest_first(by_rule),
+        by_folder: largest_first(by_folder),
+        by_day: chronological(by_day),
+        by_week: chronological(by_week),
+    }
+}
+
+/// Refresh the tray once the local date changes so "today" starts from zero
+///
+/// Polls instead of sleeping until midnight, so a machine that was suspended
+/// over midnight still rolls over within a minute of waking up.
+pub fn spawn_midnight_rollover(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut today = Local::now().date_naive();
+        loop {
+            tokio::time::sleep(ROLLOVER_CHECK).await;
+
+            let now = Local::now().date_naive();
+            if now != today {
+                today = now;
+                log::info!("New day, daily counters start over");
+                crate::refresh_tray_menu(&app);
+            }
+        }
+    });
+}
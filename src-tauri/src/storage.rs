@@ -1,68 +1,960 @@
 //! Local storage for caching rules and history.
+//!
+//! Backed by an embedded SQLite database. The schema is versioned through
+//! `PRAGMA user_version` and upgraded by the `MIGRATIONS` list on open.
 
+use chrono::NaiveDate;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::classifier::LocalRule;
+use crate::config::{AppConfig, DEFAULT_PROFILE};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct LocalStorage {
-    pub cached_rules: Vec<LocalRule>,
-    pub pending_actions: Vec<PendingAction>,
-}
+/// Schema migrations, applied in order. Index + 1 is the schema version.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE cached_rules (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        condition_type TEXT NOT NULL,
+        condition_value TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        priority INTEGER NOT NULL
+    );
+    CREATE TABLE pending_actions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        filename TEXT NOT NULL,
+        source_path TEXT NOT NULL,
+        dest_path TEXT NOT NULL,
+        confidence REAL NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE TABLE move_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        filename TEXT NOT NULL,
+        source_path TEXT NOT NULL,
+        dest_path TEXT NOT NULL,
+        category TEXT,
+        rule_id TEXT,
+        rule_name TEXT,
+        confidence REAL,
+        method TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL DEFAULT 0,
+        timestamp INTEGER NOT NULL,
+        undone INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX idx_move_history_timestamp ON move_history (timestamp);
+    CREATE TABLE processed_files (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        processed_at INTEGER NOT NULL
+    );
+    CREATE TABLE statistics (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL DEFAULT 0
+    );",
+    // 2: cached rules belong to a config profile
+    "CREATE TABLE cached_rules_v2 (
+        profile TEXT NOT NULL,
+        id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        condition_type TEXT NOT NULL,
+        condition_value TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        priority INTEGER NOT NULL,
+        PRIMARY KEY (profile, id)
+    );
+    INSERT INTO cached_rules_v2
+        (profile, id, name, condition_type, condition_value, destination, priority)
+        SELECT 'default', id, name, condition_type, condition_value, destination, priority
+        FROM cached_rules;
+    DROP TABLE cached_rules;
+    ALTER TABLE cached_rules_v2 RENAME TO cached_rules;",
+    // 3: small pieces of agent state that outlive a restart
+    "CREATE TABLE agent_state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    // 4: files that arrived while the watcher was paused
+    "CREATE TABLE paused_queue (
+        path TEXT PRIMARY KEY,
+        queued_at INTEGER NOT NULL
+    );",
+    // 5: per-day counters, keyed by local date; seeded from the move journal.
+    // `sorted` follows the journal from then on, see `record_move`
+    "CREATE TABLE daily_stats (
+        day TEXT PRIMARY KEY,
+        sorted INTEGER NOT NULL DEFAULT 0,
+        duplicates INTEGER NOT NULL DEFAULT 0,
+        skipped INTEGER NOT NULL DEFAULT 0,
+        failed INTEGER NOT NULL DEFAULT 0
+    );
+    INSERT INTO daily_stats (day, sorted)
+        SELECT date(timestamp, 'unixepoch', 'localtime'), COUNT(*)
+        FROM move_history
+        WHERE undone = 0
+        GROUP BY 1;",
+    // 6: pending actions carry everything the server log call needs
+    "ALTER TABLE pending_actions ADD COLUMN category_id TEXT;
+    ALTER TABLE pending_actions ADD COLUMN rule_id TEXT;",
+    // 7: the old key/value counters, superseded by `daily_stats`
+    "DROP TABLE statistics;",
+];
 
+/// A sorted file the server has not been told about yet
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PendingAction {
+    #[serde(default)]
+    pub id: i64,
     pub filename: String,
     pub source_path: String,
     pub dest_path: String,
+    #[serde(default)]
+    pub category_id: Option<String>,
+    #[serde(default)]
+    pub rule_id: Option<String>,
     pub confidence: f64,
     pub timestamp: i64,
 }
 
+/// One entry of the move journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveRecord {
+    #[serde(default)]
+    pub id: i64,
+    pub filename: String,
+    pub source_path: String,
+    pub dest_path: String,
+    pub category: Option<String>,
+    pub rule_id: Option<String>,
+    pub rule_name: Option<String>,
+    pub confidence: Option<f64>,
+    pub method: String,
+    pub size_bytes: u64,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// Sort order for `search_moves`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveOrder {
+    #[default]
+    Newest,
+    Oldest,
+    Filename,
+    Largest,
+    Confidence,
+}
+
+impl MoveOrder {
+    fn order_by(self) -> &'static str {
+        match self {
+            Self::Newest => "timestamp DESC, id DESC",
+            Self::Oldest => "timestamp, id",
+            Self::Filename => "filename COLLATE NOCASE, timestamp DESC",
+            Self::Largest => "size_bytes DESC, timestamp DESC",
+            Self::Confidence => "confidence DESC, timestamp DESC",
+        }
+    }
+}
+
+/// Filters and paging for `search_moves`; `None` fields do not filter
+#[derive(Debug, Clone, Default)]
+pub struct MoveQuery {
+    /// Part of the file name, case-insensitive
+    pub search: Option<String>,
+    pub category: Option<String>,
+    /// Only moves out of this folder or its subfolders
+    pub folder: Option<String>,
+    pub method: Option<String>,
+    /// Unix seconds, inclusive
+    pub from: Option<i64>,
+    /// Unix seconds, exclusive
+    pub to: Option<i64>,
+    pub include_undone: bool,
+    pub order: MoveOrder,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Escape `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern
+fn like_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Counters kept per local calendar day
+///
+/// Sorted files are not counted here but by the journal, so an undo takes
+/// them back off.
+#[derive(Debug, Clone, Copy)]
+pub enum DailyCounter {
+    Duplicates,
+    Skipped,
+    Failed,
+}
+
+impl DailyCounter {
+    fn column(self) -> &'static str {
+        match self {
+            Self::Duplicates => "duplicates",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// One day of counters; `day` is a local date, `YYYY-MM-DD`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailyStats {
+    pub day: String,
+    pub sorted: u32,
+    pub duplicates: u32,
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+/// A file that arrived during a pause and waits to be sorted
+#[derive(Debug, Clone, Serialize)]
+pub struct QueuedFile {
+    pub path: String,
+    pub queued_at: i64,
+}
+
+/// `agent_state` key set once `storage.json` has been imported
+const LEGACY_IMPORTED: &str = "legacy_imported";
+
+/// Shape of the legacy `storage.json`, only used for the one-time import
+#[derive(Debug, Deserialize, Default)]
+struct LegacyStorage {
+    #[serde(default)]
+    cached_rules: Vec<LocalRule>,
+    #[serde(default)]
+    pending_actions: Vec<PendingAction>,
+}
+
+pub struct LocalStorage {
+    conn: Connection,
+}
+
 impl LocalStorage {
-    fn storage_path() -> PathBuf {
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("filesorter");
-        
-        fs::create_dir_all(&config_dir).ok();
-        config_dir.join("storage.json")
-    }
-
-    pub fn load() -> Self {
-        let path = Self::storage_path();
-        
-        if !path.exists() {
-            return Self::default();
+    fn storage_dir() -> PathBuf {
+        AppConfig::config_dir()
+    }
+
+    /// Open the database, migrating it and importing `storage.json` if needed.
+    pub fn open() -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = Self::storage_dir();
+        let conn = Connection::open(dir.join("storage.db"))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+
+        let mut storage = Self { conn };
+        storage.migrate()?;
+
+        // Retried on every start until it succeeds
+        if storage.agent_state(LEGACY_IMPORTED).is_none() {
+            if let Err(e) = storage.import_legacy_json(&dir.join("storage.json")) {
+                log::warn!("Could not import legacy storage: {}", e);
+            }
+        }
+
+        Ok(storage)
+    }
+
+    /// Fallback when the database cannot be opened; nothing is persisted.
+    pub fn in_memory() -> Self {
+        let conn = Connection::open_in_memory().expect("in-memory SQLite is always available");
+        let mut storage = Self { conn };
+        storage.migrate().expect("migrations apply to an empty database");
+        storage
+    }
+
+    /// Apply pending migrations, returning the version found on open.
+    fn migrate(&mut self) -> rusqlite::Result<usize> {
+        let version: usize = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", i + 1)?;
+            tx.commit()?;
+            log::info!("Migrated local storage to schema version {}", i + 1);
         }
 
-        fs::read_to_string(&path)
-            .ok()
-            .and_then(|content| serde_json::from_str(&content).ok())
-            .unwrap_or_default()
+        Ok(version)
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = Self::storage_path();
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content)?;
+    /// Import rules and pending actions from the old JSON store, once.
+    fn import_legacy_json(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(path)?;
+        let legacy: LegacyStorage = match serde_json::from_str(&content) {
+            Ok(legacy) => legacy,
+            Err(e) => {
+                // Keep the file around so nothing is lost
+                log::warn!("Could not import {}: {}", path.display(), e);
+                return Ok(());
+            }
+        };
+
+        // The flag commits with the data, so a retry never imports twice
+        self.conn.execute_batch("BEGIN")?;
+        self.cache_rules(DEFAULT_PROFILE, legacy.cached_rules);
+        for action in legacy.pending_actions {
+            self.add_pending_action(action);
+        }
+        self.set_agent_state(LEGACY_IMPORTED, Some("1"));
+        if let Err(e) = self.conn.execute_batch("COMMIT") {
+            self.conn.execute_batch("ROLLBACK").ok();
+            return Err(e.into());
+        }
+
+        if let Err(e) = fs::rename(path, path.with_extension("json.imported")) {
+            log::warn!("Could not rename {}: {}", path.display(), e);
+        }
+        log::info!("Imported legacy storage from {}", path.display());
         Ok(())
     }
 
-    pub fn cache_rules(&mut self, rules: Vec<LocalRule>) {
-        self.cached_rules = rules;
-        self.save().ok();
+    /// Replace the cached rules of a config profile
+    pub fn cache_rules(&mut self, profile: &str, rules: Vec<LocalRule>) {
+        let result: rusqlite::Result<()> = (|| {
+            let tx = self.conn.transaction()?;
+            tx.execute("DELETE FROM cached_rules WHERE profile = ?1", params![profile])?;
+            for rule in &rules {
+                tx.execute(
+                    "INSERT OR REPLACE INTO cached_rules
+                        (profile, id, name, condition_type, condition_value, destination, priority)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        profile,
+                        rule.id,
+                        rule.name,
+                        rule.condition_type,
+                        rule.condition_value.to_string(),
+                        rule.destination,
+                        rule.priority,
+                    ],
+                )?;
+            }
+            tx.commit()
+        })();
+
+        if let Err(e) = result {
+            log::error!("Failed to cache rules: {}", e);
+        }
+    }
+
+    /// Cached rules of a config profile, highest priority first
+    pub fn cached_rules(&self, profile: &str) -> Vec<LocalRule> {
+        let result: rusqlite::Result<Vec<LocalRule>> = (|| {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, name, condition_type, condition_value, destination, priority
+                 FROM cached_rules WHERE profile = ?1 ORDER BY priority DESC",
+            )?;
+            let rows = stmt.query_map(params![profile], |row| {
+                let condition_value: String = row.get(3)?;
+                Ok(LocalRule {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    condition_type: row.get(2)?,
+                    condition_value: serde_json::from_str(&condition_value)
+                        .unwrap_or(serde_json::Value::Null),
+                    destination: row.get(4)?,
+                    priority: row.get(5)?,
+                })
+            })?;
+            rows.collect()
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to read cached rules: {}", e);
+            Vec::new()
+        })
     }
 
     pub fn add_pending_action(&mut self, action: PendingAction) {
-        self.pending_actions.push(action);
-        self.save().ok();
+        let result = self.conn.execute(
+            "INSERT INTO pending_actions
+                (filename, source_path, dest_path, category_id, rule_id, confidence, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                action.filename,
+                action.source_path,
+                action.dest_path,
+                action.category_id,
+                action.rule_id,
+                action.confidence,
+                action.timestamp,
+            ],
+        );
+
+        if let Err(e) = result {
+            log::error!("Failed to queue pending action: {}", e);
+        }
     }
 
-    pub fn clear_pending_actions(&mut self) {
-        self.pending_actions.clear();
-        self.save().ok();
+    /// Actions waiting to be sent to the server, oldest first
+    pub fn pending_actions(&self) -> Vec<PendingAction> {
+        let result: rusqlite::Result<Vec<PendingAction>> = (|| {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, filename, source_path, dest_path, category_id, rule_id, confidence, timestamp
+                 FROM pending_actions ORDER BY id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(PendingAction {
+                    id: row.get(0)?,
+                    filename: row.get(1)?,
+                    source_path: row.get(2)?,
+                    dest_path: row.get(3)?,
+                    category_id: row.get(4)?,
+                    rule_id: row.get(5)?,
+                    confidence: row.get(6)?,
+                    timestamp: row.get(7)?,
+                })
+            })?;
+            rows.collect()
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to read pending actions: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Forget a pending action once the server has it
+    pub fn remove_pending_action(&mut self, id: i64) {
+        if let Err(e) = self.conn.execute("DELETE FROM pending_actions WHERE id = ?1", params![id]) {
+            log::error!("Failed to remove pending action {}: {}", id, e);
+        }
+    }
+
+    /// Append a move to the journal and return its id.
+    pub fn record_move(&mut self, record: &MoveRecord) -> Option<i64> {
+        let result = self.conn.execute(
+            "INSERT INTO move_history
+                (filename, source_path, dest_path, category, rule_id, rule_name,
+                 confidence, method, size_bytes, timestamp, undone)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                record.filename,
+                record.source_path,
+                record.dest_path,
+                record.category,
+                record.rule_id,
+                record.rule_name,
+                record.confidence,
+                record.method,
+                record.size_bytes as i64,
+                record.timestamp,
+                record.undone,
+            ],
+        );
+
+        match result {
+            Ok(_) => {
+                let id = self.conn.last_insert_rowid();
+                if !record.undone {
+                    self.count_sorted(id, 1);
+                }
+                Some(id)
+            }
+            Err(e) => {
+                log::error!("Failed to record move of {}: {}", record.filename, e);
+                None
+            }
+        }
+    }
+
+    /// Moves still in effect with `from <= timestamp < to`, oldest first
+    pub fn moves_between(&self, from: Option<i64>, to: Option<i64>) -> Vec<MoveRecord> {
+        let result: rusqlite::Result<Vec<MoveRecord>> = (|| {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, filename, source_path, dest_path, category, rule_id, rule_name,
+                        confidence, method, size_bytes, timestamp, undone
+                 FROM move_history
+                 WHERE undone = 0 AND timestamp >= ?1 AND timestamp < ?2
+                 ORDER BY timestamp, id",
+            )?;
+            let rows = stmt.query_map(
+                params![from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)],
+                move_record_from_row,
+            )?;
+            rows.collect()
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to read move history: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// One page of the journal matching `query`, and the number of matches overall
+    pub fn search_moves(&self, query: &MoveQuery) -> (Vec<MoveRecord>, u64) {
+        let mut conditions = Vec::new();
+        let mut values: Vec<SqlValue> = Vec::new();
+
+        if !query.include_undone {
+            conditions.push("undone = 0".to_string());
+        }
+        if let Some(search) = &query.search {
+            values.push(SqlValue::Text(format!("%{}%", like_escape(search))));
+            conditions.push(format!("filename LIKE ?{} ESCAPE '\\'", values.len()));
+        }
+        if let Some(category) = &query.category {
+            values.push(SqlValue::Text(category.clone()));
+            conditions.push(format!("category = ?{}", values.len()));
+        }
+        if let Some(folder) = &query.folder {
+            let folder = folder.trim_end_matches(['/', '\\']);
+            let prefix = format!("{}{}", folder, std::path::MAIN_SEPARATOR);
+            values.push(SqlValue::Text(format!("{}%", like_escape(&prefix))));
+            conditions.push(format!("source_path LIKE ?{} ESCAPE '\\'", values.len()));
+        }
+        if let Some(method) = &query.method {
+            values.push(SqlValue::Text(method.clone()));
+            conditions.push(format!("method = ?{}", values.len()));
+        }
+        if let Some(from) = query.from {
+            values.push(SqlValue::Integer(from));
+            conditions.push(format!("timestamp >= ?{}", values.len()));
+        }
+        if let Some(to) = query.to {
+            values.push(SqlValue::Integer(to));
+            conditions.push(format!("timestamp < ?{}", values.len()));
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let result: rusqlite::Result<(Vec<MoveRecord>, u64)> = (|| {
+            let total: i64 = self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM move_history {}", filter),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT id, filename, source_path, dest_path, category, rule_id, rule_name,
+                        confidence, method, size_bytes, timestamp, undone
+                 FROM move_history {} ORDER BY {} LIMIT {} OFFSET {}",
+                filter,
+                query.order.order_by(),
+                query.limit,
+                query.offset
+            ))?;
+            let rows = stmt.query_map(params_from_iter(values.iter()), move_record_from_row)?;
+            Ok((rows.collect::<rusqlite::Result<_>>()?, total as u64))
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to search move history: {}", e);
+            (Vec::new(), 0)
+        })
+    }
+
+    /// Whether any journal entry moved a file to `dest_path`
+    pub fn has_move_to(&self, dest_path: &str) -> bool {
+        self.conn
+            .query_row(
+                "SELECT 1 FROM move_history WHERE dest_path = ?1 LIMIT 1",
+                params![dest_path],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|found| found.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Most recent move that has not been undone yet.
+    pub fn last_move(&self) -> Option<MoveRecord> {
+        self.conn
+            .query_row(
+                "SELECT id, filename, source_path, dest_path, category, rule_id, rule_name,
+                        confidence, method, size_bytes, timestamp, undone
+                 FROM move_history WHERE undone = 0
+                 ORDER BY timestamp DESC, id DESC LIMIT 1",
+                [],
+                move_record_from_row,
+            )
+            .optional()
+            .unwrap_or_else(|e| {
+                log::error!("Failed to read move history: {}", e);
+                None
+            })
+    }
+
+    pub fn mark_undone(&mut self, id: i64) {
+        self.set_undone(id, 1, "undone");
+    }
+
+    /// Drop a move whose sorted file is gone from the undo chain.
+    ///
+    /// Stored as `undone = 2`, so it reads as undone but stays distinguishable.
+    pub fn mark_missing(&mut self, id: i64) {
+        self.set_undone(id, 2, "missing");
+    }
+
+    fn set_undone(&mut self, id: i64, value: i64, what: &str) {
+        let result = self.conn.execute(
+            "UPDATE move_history SET undone = ?2 WHERE id = ?1 AND undone = 0",
+            params![id, value],
+        );
+
+        match result {
+            // Only a move that still counted comes off its day's counter
+            Ok(changed) if changed > 0 => self.count_sorted(id, -1),
+            Ok(_) => {}
+            Err(e) => log::error!("Failed to mark move {} as {}: {}", id, what, e),
+        }
+    }
+
+    /// Adjust the `sorted` counter of the local day a journal entry was made on
+    fn count_sorted(&mut self, id: i64, delta: i64) {
+        let result = self.conn.execute(
+            "INSERT INTO daily_stats (day, sorted)
+                SELECT date(timestamp, 'unixepoch', 'localtime'), MAX(?2, 0)
+                FROM move_history WHERE id = ?1
+             ON CONFLICT(day) DO UPDATE SET sorted = MAX(sorted + ?2, 0)",
+            params![id, delta],
+        );
+
+        if let Err(e) = result {
+            log::error!("Failed to update the daily sorted counter: {}", e);
+        }
+    }
+
+    /// Whether this exact file (path, size and mtime) was handled before.
+    pub fn is_processed(&self, path: &str, size: u64, modified: i64) -> bool {
+        self.conn
+            .query_row(
+                "SELECT 1 FROM processed_files WHERE path = ?1 AND size = ?2 AND modified = ?3",
+                params![path, size as i64, modified],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|found| found.is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn mark_processed(&mut self, path: &str, size: u64, modified: i64) {
+        let result = self.conn.execute(
+            "INSERT OR REPLACE INTO processed_files (path, size, modified, processed_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![path, size as i64, modified, chrono::Utc::now().timestamp()],
+        );
+
+        if let Err(e) = result {
+            log::error!("Failed to mark {} as processed: {}", path, e);
+        }
+    }
+
+    /// Hold a file back until the watcher resumes; `false` if it already was
+    pub fn queue_paused(&mut self, path: &str) -> bool {
+        let result = self.conn.execute(
+            "INSERT OR IGNORE INTO paused_queue (path, queued_at) VALUES (?1, ?2)",
+            params![path, chrono::Utc::now().timestamp()],
+        );
+
+        result.map(|inserted| inserted > 0).unwrap_or_else(|e| {
+            log::error!("Failed to queue {}: {}", path, e);
+            false
+        })
+    }
+
+    /// Files held back during pauses, oldest first
+    pub fn paused_queue(&self) -> Vec<QueuedFile> {
+        let result: rusqlite::Result<Vec<QueuedFile>> = (|| {
+            let mut stmt = self
+                .conn
+                .prepare("SELECT path, queued_at FROM paused_queue ORDER BY queued_at, path")?;
+            let rows = stmt.query_map([], |row| {
+                Ok(QueuedFile {
+                    path: row.get(0)?,
+                    queued_at: row.get(1)?,
+                })
+            })?;
+            rows.collect()
+        })();
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to read the paused queue: {}", e);
+            Vec::new()
+        })
+    }
+
+    pub fn is_queued(&self, path: &str) -> bool {
+        self.conn
+            .query_row("SELECT 1 FROM paused_queue WHERE path = ?1", params![path], |_| Ok(()))
+            .optional()
+            .unwrap_or_else(|e| {
+                log::error!("Failed to look up {} in the paused queue: {}", path, e);
+                None
+            })
+            .is_some()
+    }
+
+    /// Take a file off the paused queue; `false` if it was not queued
+    pub fn unqueue_paused(&mut self, path: &str) -> bool {
+        let result = self
+            .conn
+            .execute("DELETE FROM paused_queue WHERE path = ?1", params![path]);
+
+        result.map(|removed| removed > 0).unwrap_or_else(|e| {
+            log::error!("Failed to remove {} from the paused queue: {}", path, e);
+            false
+        })
+    }
+
+    pub fn count_daily(&mut self, day: NaiveDate, counter: DailyCounter) {
+        let column = counter.column();
+        let result = self.conn.execute(
+            &format!(
+                "INSERT INTO daily_stats (day, {column}) VALUES (?1, 1)
+                 ON CONFLICT(day) DO UPDATE SET {column} = {column} + 1"
+            ),
+            params![day.to_string()],
+        );
+
+        if let Err(e) = result {
+            log::error!("Failed to update daily {} counter: {}", column, e);
+        }
+    }
+
+    /// Counters for every day from `from` to `to` inclusive, zero for quiet days
+    pub fn daily_history(&self, from: NaiveDate, to: NaiveDate) -> Vec<DailyStats> {
+        let result: rusqlite::Result<Vec<DailyStats>> = (|| {
+            let mut stmt = self.conn.prepare(
+                "SELECT day, sorted, duplicates, skipped, failed FROM daily_stats
+                 WHERE day BETWEEN ?1 AND ?2",
+            )?;
+            let rows = stmt.query_map(params![from.to_string(), to.to_string()], |row| {
+                Ok(DailyStats {
+                    day: row.get(0)?,
+                    sorted: row.get(1)?,
+                    duplicates: row.get(2)?,
+                    skipped: row.get(3)?,
+                    failed: row.get(4)?,
+                })
+            })?;
+            rows.collect()
+        })();
+
+        let stored = result.unwrap_or_else(|e| {
+            log::error!("Failed to read daily statistics: {}", e);
+            Vec::new()
+        });
+
+        from.iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| {
+                let day = day.to_string();
+                stored
+                    .iter()
+                    .find(|stats| stats.day == day)
+                    .cloned()
+                    .unwrap_or(DailyStats { day, ..Default::default() })
+            })
+            .collect()
+    }
+
+    pub fn daily_stats(&self, day: NaiveDate) -> DailyStats {
+        self.daily_history(day, day).pop().unwrap_or_default()
+    }
+
+    pub fn agent_state(&self, key: &str) -> Option<String> {
+        self.conn
+            .query_row(
+                "SELECT value FROM agent_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap_or_else(|e| {
+                log::error!("Failed to read agent state {}: {}", key, e);
+                None
+            })
+    }
+
+    /// Store a piece of agent state, or remove it when `value` is `None`
+    pub fn set_agent_state(&mut self, key: &str, value: Option<&str>) {
+        let result = match value {
+            Some(value) => self.conn.execute(
+                "INSERT OR REPLACE INTO agent_state (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            ),
+            None => self
+                .conn
+                .execute("DELETE FROM agent_state WHERE key = ?1", params![key]),
+        };
+
+        if let Err(e) = result {
+            log::error!("Failed to save agent state {}: {}", key, e);
+        }
+    }
+
+}
+
+fn move_record_from_row(row: &rusqlite::Row) -> rusqlite::Result<MoveRecord> {
+    Ok(MoveRecord {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        source_path: row.get(2)?,
+        dest_path: row.get(3)?,
+        category: row.get(4)?,
+        rule_id: row.get(5)?,
+        rule_name: row.get(6)?,
+        confidence: row.get(7)?,
+        method: row.get(8)?,
+        size_bytes: row.get::<_, i64>(9)? as u64,
+        timestamp: row.get(10)?,
+        undone: row.get(11)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(filename: &str, timestamp: i64) -> MoveRecord {
+        MoveRecord {
+            id: 0,
+            filename: filename.to_string(),
+            source_path: format!("/watched/{}", filename),
+            dest_path: format!("/watched/Docs/{}", filename),
+            category: Some("Docs".to_string()),
+            rule_id: None,
+            rule_name: None,
+            confidence: Some(0.9),
+            method: "rule".to_string(),
+            size_bytes: 10,
+            timestamp,
+            undone: false,
+        }
+    }
+
+    #[test]
+    fn sorted_counter_follows_the_journal() {
+        let mut storage = LocalStorage::in_memory();
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+
+        let first = storage.record_move(&record("a.pdf", now.timestamp())).unwrap();
+        let second = storage.record_move(&record("b.pdf", now.timestamp())).unwrap();
+        assert_eq!(storage.daily_stats(today).sorted, 2);
+
+        storage.mark_undone(second);
+        storage.mark_missing(first);
+        assert_eq!(storage.daily_stats(today).sorted, 0);
+
+        // Marking again must not take it below what was counted
+        storage.mark_undone(first);
+        storage.record_move(&record("c.pdf", now.timestamp()));
+        assert_eq!(storage.daily_stats(today).sorted, 1);
+    }
+
+    #[test]
+    fn like_escape_escapes_wildcards() {
+        assert_eq!(like_escape("100%_done\\x"), "100\\%\\_done\\\\x");
+        assert_eq!(like_escape("plain"), "plain");
+    }
+
+    fn search(storage: &LocalStorage, query: MoveQuery) -> (Vec<String>, u64) {
+        let (records, total) = storage.search_moves(&MoveQuery { limit: 10, ..query });
+        (records.into_iter().map(|r| r.filename).collect(), total)
+    }
+
+    #[test]
+    fn search_moves_filters_sorts_and_pages() {
+        let mut storage = LocalStorage::in_memory();
+        let mut other = record("Report_2024.pdf", 300);
+        other.source_path = "/elsewhere/Report_2024.pdf".to_string();
+        other.method = "ai".to_string();
+        storage.record_move(&record("report2024.pdf", 100));
+        storage.record_move(&record("100%.txt", 200));
+        storage.record_move(&other);
+        let undone = storage.record_move(&record("undone.pdf", 400)).unwrap();
+        storage.mark_undone(undone);
+
+        // `_` and `%` match literally, and case does not matter
+        assert_eq!(
+            search(&storage, MoveQuery { search: Some("rt_2".to_string()), ..Default::default() }),
+            (vec!["Report_2024.pdf".to_string()], 1)
+        );
+        assert_eq!(
+            search(&storage, MoveQuery { search: Some("%".to_string()), ..Default::default() }),
+            (vec!["100%.txt".to_string()], 1)
+        );
+        assert_eq!(
+            search(&storage, MoveQuery { search: Some("REPORT".to_string()), ..Default::default() }).1,
+            2
+        );
+
+        assert_eq!(
+            search(&storage, MoveQuery { folder: Some("/watched/".to_string()), ..Default::default() }),
+            (vec!["100%.txt".to_string(), "report2024.pdf".to_string()], 2)
+        );
+        assert_eq!(
+            search(&storage, MoveQuery { method: Some("ai".to_string()), ..Default::default() }).0,
+            vec!["Report_2024.pdf".to_string()]
+        );
+        assert_eq!(
+            search(&storage, MoveQuery { from: Some(200), to: Some(300), ..Default::default() }).0,
+            vec!["100%.txt".to_string()]
+        );
+        assert_eq!(
+            search(&storage, MoveQuery { include_undone: true, ..Default::default() }).1,
+            4
+        );
+
+        // The total counts every match, not just the page
+        let (records, total) = storage.search_moves(&MoveQuery {
+            order: MoveOrder::Oldest,
+            limit: 1,
+            offset: 1,
+            ..Default::default()
+        });
+        assert_eq!(total, 3);
+        assert_eq!(records[0].filename, "100%.txt");
+    }
+
+    #[test]
+    fn pending_actions_are_kept_until_removed() {
+        let mut storage = LocalStorage::in_memory();
+        for name in ["a.pdf", "b.pdf"] {
+            storage.add_pending_action(PendingAction {
+                id: 0,
+                filename: name.to_string(),
+                source_path: format!("/watched/{}", name),
+                dest_path: format!("/watched/Docs/{}", name),
+                category_id: None,
+                rule_id: Some("r1".to_string()),
+                confidence: 0.8,
+                timestamp: 100,
+            });
+        }
+
+        let pending = storage.pending_actions();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].filename, "a.pdf");
+        assert_eq!(pending[0].rule_id.as_deref(), Some("r1"));
+
+        storage.remove_pending_action(pending[0].id);
+        let pending = storage.pending_actions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].filename, "b.pdf");
     }
 }
@@ -0,0 +1,53 @@
+//! Reverting moves recorded in the local journal.
+
+use std::fs;
+use std::path::Path;
+
+use crate::duplicates::DuplicateIndex;
+use crate::file_watcher;
+use crate::storage::{LocalStorage, MoveRecord};
+
+/// Move the most recently sorted file back to where it came from.
+pub fn undo_last(
+    storage: &mut LocalStorage,
+    duplicate_index: &mut DuplicateIndex,
+) -> Result<MoveRecord, String> {
+    let record = storage
+        .last_move()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+
+    let source = Path::new(&record.source_path);
+    let dest = Path::new(&record.dest_path);
+
+    if !dest.exists() {
+        // Otherwise every later undo would stop at this same record
+        storage.mark_missing(record.id);
+        log::warn!("Dropped move of {} from undo history: file is gone", record.filename);
+        return Err(format!(
+            "{} no longer exists and was removed from the undo history; undo again to revert the move before it",
+            record.dest_path
+        ));
+    }
+    if source.exists() {
+        return Err(format!("{} is already taken", record.source_path));
+    }
+
+    if let Some(parent) = source.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::rename(dest, source).map_err(|e| format!("Failed to move file back: {}", e))?;
+
+    storage.mark_undone(record.id);
+
+    // The watcher sees the file reappear; make sure it is not sorted again
+    if let Some((size, modified)) = file_watcher::file_identity(source) {
+        storage.mark_processed(&record.source_path, size, modified);
+    }
+
+    duplicate_index.remove(dest);
+    duplicate_index.insert(source);
+    duplicate_index.save().ok();
+
+    log::info!("Undid move of {} back to {}", record.filename, record.source_path);
+    Ok(record)
+}
@@ -1,26 +1,52 @@
+import { listen } from '@tauri-apps/api/event'
 import { open } from '@tauri-apps/api/shell'
 import { invoke } from '@tauri-apps/api/tauri'
 import { useEffect, useState } from 'react'
-import Dashboard from './components/Dashboard'
+import Dashboard, { PauseDuration } from './components/Dashboard'
+import History from './components/History'
 import Login from './components/Login'
+import Logs from './components/Logs'
 import Settings from './components/Settings'
+import { EventPayloads } from './events'
 
 interface AppStatus {
   is_paused: boolean
+  paused_until: string | null
+  paused_queue: number
+  is_online: boolean | null
   files_today: number
   is_logged_in: boolean
   watched_folders: string[]
+  config_recovery: {
+    reason: string
+    restored_from: string | null
+    corrupt_copy: string | null
+  } | null
 }
 
 function App() {
   const [status, setStatus] = useState<AppStatus | null>(null)
-  const [activeTab, setActiveTab] = useState<'dashboard' | 'settings'>('dashboard')
+  const [activeTab, setActiveTab] = useState<'dashboard' | 'history' | 'logs' | 'settings'>('dashboard')
   const [loading, setLoading] = useState(true)
 
   useEffect(() => {
     loadStatus()
   }, [])
 
+  // The backend announces every change that affects the status
+  useEffect(() => {
+    const events: (keyof EventPayloads)[] = [
+      'watcher-state-changed',
+      'queue-changed',
+      'connectivity-changed',
+      'file-sorted',
+    ]
+    const unlisteners = events.map((event) => listen(event, () => loadStatus()))
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((f) => f()))
+    }
+  }, [])
+
   const loadStatus = async () => {
     try {
       const result = await invoke<AppStatus>('get_status')
@@ -50,6 +76,16 @@ function App() {
     await loadStatus()
   }
 
+  const handlePauseFor = async (duration: PauseDuration) => {
+    await invoke('pause_for', { duration })
+    await loadStatus()
+  }
+
+  const handleDismissRecovery = async () => {
+    await invoke('dismiss_config_recovery')
+    loadStatus()
+  }
+
   const handleOpenDashboard = async () => {
     const url = await invoke<string>('open_dashboard')
     await open(url)
@@ -76,11 +112,26 @@ function App() {
           <span className="icon">📁</span>
           <h1>FileSorter</h1>
         </div>
-        <div className={`status-badge ${status.is_paused ? 'paused' : 'active'}`}>
-          {status.is_paused ? '⏸️ Пауза' : '✅ Активен'}
-        </div>
+        {status.is_online === false ? (
+          <div className="status-badge offline">⚠️ Нет связи</div>
+        ) : (
+          <div className={`status-badge ${status.is_paused ? 'paused' : 'active'}`}>
+            {status.is_paused ? '⏸️ Пауза' : '✅ Активен'}
+          </div>
+        )}
       </header>
 
+      {status.config_recovery && (
+        <div className="card" style={{ borderColor: 'var(--warning)', color: 'var(--warning)', fontSize: '0.8rem' }}>
+          {status.config_recovery.restored_from
+            ? '⚠️ Файл настроек был повреждён, восстановлена резервная копия'
+            : '⚠️ Файл настроек был повреждён, настройки сброшены'}
+          <button className="btn btn-secondary" onClick={handleDismissRecovery} style={{ width: 'auto', marginLeft: '0.5rem' }}>
+            ✕
+          </button>
+        </div>
+      )}
+
       <div className="tabs">
         <button
           className={`tab ${activeTab === 'dashboard' ? 'active' : ''}`}
@@ -88,6 +139,18 @@ function App() {
         >
           📊 Статус
         </button>
+        <button
+          className={`tab ${activeTab === 'history' ? 'active' : ''}`}
+          onClick={() => setActiveTab('history')}
+        >
+          🕘 История
+        </button>
+        <button
+          className={`tab ${activeTab === 'logs' ? 'active' : ''}`}
+          onClick={() => setActiveTab('logs')}
+        >
+          📜 Журнал
+        </button>
         <button
           className={`tab ${activeTab === 'settings' ? 'active' : ''}`}
           onClick={() => setActiveTab('settings')}
@@ -96,15 +159,20 @@ function App() {
         </button>
       </div>
 
+      {activeTab === 'history' && <History />}
+      {activeTab === 'logs' && <Logs />}
+
       {activeTab === 'dashboard' ? (
         <Dashboard
           status={status}
           onTogglePause={handleTogglePause}
+          onPauseFor={handlePauseFor}
+          onRefresh={loadStatus}
           onOpenDashboard={handleOpenDashboard}
         />
-      ) : (
+      ) : activeTab === 'settings' ? (
         <Settings onLogout={handleLogout} onRefresh={loadStatus} />
-      )}
+      ) : null}
     </div>
   )
 }
@@ -1,20 +1,77 @@
-import { open as openDialog } from '@tauri-apps/api/dialog'
-import { Body, fetch as httpFetch } from '@tauri-apps/api/http'
+import { listen } from '@tauri-apps/api/event'
 import { invoke } from '@tauri-apps/api/tauri'
-import { useEffect, useState } from 'react'
+import { useEffect, useRef, useState } from 'react'
+import { FileFailed, FileSkipped } from '../events'
+import { HistoryPage } from './History'
+import { LogEntry } from './Logs'
 
-const API_URL = 'http://localhost:8085'
+export type PauseDuration = 'fifteen_minutes' | 'one_hour' | 'until_tomorrow' | 'indefinitely'
+
+// A burst of sorted files reloads the recent actions once it settles
+const RELOAD_DELAY_MS = 500
+
+const PAUSE_OPTIONS: { duration: PauseDuration; label: string }[] = [
+  { duration: 'fifteen_minutes', label: '15 мин' },
+  { duration: 'one_hour', label: '1 час' },
+  { duration: 'until_tomorrow', label: 'До завтра' },
+  { duration: 'indefinitely', label: 'Без срока' },
+]
 
 interface DashboardProps {
   status: {
     is_paused: boolean
+    paused_until: string | null
+    paused_queue: number
     files_today: number
     watched_folders: string[]
   }
   onTogglePause: () => Promise<void>
+  onPauseFor: (duration: PauseDuration) => Promise<void>
+  onRefresh: () => Promise<void>
   onOpenDashboard: () => Promise<void>
 }
 
+interface QueuedFile {
+  path: string
+  queued_at: number
+}
+
+interface StatBucket {
+  key: string
+  label: string | null
+  files: number
+  bytes: number
+  average_confidence: number | null
+}
+
+interface LocalStatistics {
+  total: StatBucket
+  methods: Record<string, number>
+  by_category: StatBucket[]
+}
+
+const formatBytes = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} Б`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} КБ`
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} МБ`
+  return `${(bytes / 1024 / 1024 / 1024).toFixed(1)} ГБ`
+}
+
+interface DailyStats {
+  day: string
+  sorted: number
+  duplicates: number
+  skipped: number
+  failed: number
+}
+
+// A file the watcher could not sort, as reported by the backend
+interface Problem {
+  path: string
+  message: string
+  file_id: string | null
+}
+
 interface Action {
   id: string
   filename: string
@@ -44,6 +101,7 @@ interface ScannedFile {
   size_bytes: number
   path: string
   modified: string | null
+  duplicate_of: string | null
 }
 
 interface FolderSuggestion {
@@ -82,7 +140,7 @@ interface SuggestedRule {
 
 type OrganizeStep = 'idle' | 'scanning' | 'analyzing' | 'preview' | 'executing' | 'done'
 
-function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
+function Dashboard({ status, onTogglePause, onPauseFor, onRefresh, onOpenDashboard }: DashboardProps) {
   const [recentActions, setRecentActions] = useState<Action[]>([])
   const [loading, setLoading] = useState(true)
   const [showAutoOrganize, setShowAutoOrganize] = useState(false)
@@ -100,6 +158,11 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
   const [suggestedRules, setSuggestedRules] = useState<SuggestedRule[]>([])
   const [showRulesModal, setShowRulesModal] = useState(false)
   const [stats, setStats] = useState<Stats | null>(null)
+  const [pausedQueue, setPausedQueue] = useState<QueuedFile[]>([])
+  const [problems, setProblems] = useState<Problem[]>([])
+  const [problemLog, setProblemLog] = useState<{ file_id: string; entries: LogEntry[] } | null>(null)
+  const [dailyHistory, setDailyHistory] = useState<DailyStats[]>([])
+  const [localStats, setLocalStats] = useState<LocalStatistics | null>(null)
 
   useEffect(() => {
     loadRecentActions()
@@ -107,10 +170,113 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
     loadStats()
   }, [])
 
+  useEffect(() => {
+    loadPausedQueue()
+  }, [status.paused_queue])
+
+  useEffect(() => {
+    loadDailyHistory()
+    loadLocalStats()
+  }, [status.files_today])
+
+  // Last 7 days, from the local move history
+  const loadLocalStats = async () => {
+    const from = new Date(Date.now() - 6 * 24 * 60 * 60 * 1000)
+    const day = `${from.getFullYear()}-${String(from.getMonth() + 1).padStart(2, '0')}-${String(from.getDate()).padStart(2, '0')}`
+    try {
+      setLocalStats(await invoke<LocalStatistics>('get_statistics', { query: { from: day } }))
+    } catch (error) {
+      console.error('Failed to load local statistics:', error)
+    }
+  }
+
+  const loadDailyHistory = async () => {
+    try {
+      setDailyHistory(await invoke<DailyStats[]>('get_daily_history', { days: 14 }))
+    } catch (error) {
+      console.error('Failed to load daily history:', error)
+    }
+  }
+
+  const reloadTimer = useRef<ReturnType<typeof setTimeout>>()
+
+  useEffect(() => {
+    const scheduleReload = () => {
+      clearTimeout(reloadTimer.current)
+      reloadTimer.current = setTimeout(loadRecentActions, RELOAD_DELAY_MS)
+    }
+    const addProblem = (problem: Problem) => setProblems((current) => [problem, ...current].slice(0, 5))
+
+    const unlisteners = [
+      listen('file-sorted', scheduleReload),
+      listen<FileSkipped>('file-skipped', ({ payload }) =>
+        addProblem({
+          path: payload.path,
+          message: payload.duplicate_of ? `дубликат ${payload.duplicate_of}` : payload.reason,
+          file_id: payload.file_id,
+        })
+      ),
+      listen<FileFailed>('file-failed', ({ payload }) =>
+        addProblem({ path: payload.path, message: payload.error, file_id: payload.file_id })
+      ),
+    ]
+    return () => {
+      clearTimeout(reloadTimer.current)
+      unlisteners.forEach((unlisten) => unlisten.then((f) => f()))
+    }
+  }, [])
+
+  // Show everything logged while the file was handled, or hide it again
+  const toggleProblemLog = async (fileId: string) => {
+    if (problemLog?.file_id === fileId) {
+      setProblemLog(null)
+      return
+    }
+    try {
+      const entries = await invoke<LogEntry[]>('get_logs', { query: { file_id: fileId, level: 'debug' } })
+      setProblemLog({ file_id: fileId, entries })
+    } catch (error) {
+      console.error('Failed to load logs:', error)
+    }
+  }
+
+  const loadPausedQueue = async () => {
+    try {
+      setPausedQueue(await invoke<QueuedFile[]>('get_paused_queue'))
+    } catch (error) {
+      console.error('Failed to load paused queue:', error)
+    }
+  }
+
+  const handleSortQueued = async (paths?: string[]) => {
+    try {
+      await invoke('sort_paused_files', { paths: paths ?? null })
+    } catch (error) {
+      alert(`Не удалось отсортировать: ${error}`)
+    }
+    await onRefresh()
+    await loadPausedQueue()
+  }
+
+  const handleDiscardQueued = async (paths?: string[]) => {
+    await invoke('discard_paused_files', { paths: paths ?? null })
+    await onRefresh()
+    await loadPausedQueue()
+  }
+
   const loadRecentActions = async () => {
     try {
-      const result = await invoke<{ actions: Action[] }>('get_recent_actions')
-      setRecentActions(result.actions || [])
+      // Local history first, so recent actions still show when offline
+      const result = await invoke<HistoryPage>('get_history', { query: { per_page: 5 } })
+      setRecentActions(
+        result.entries.slice(0, 5).map((entry) => ({
+          id: `${entry.origin}-${entry.id}`,
+          filename: entry.filename,
+          dest_path: entry.dest_path,
+          category_icon: entry.category_icon,
+          created_at: new Date(entry.timestamp * 1000).toISOString(),
+        }))
+      )
     } catch (error) {
       console.error('Failed to load actions:', error)
     } finally {
@@ -129,30 +295,18 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
 
   const loadStats = async () => {
     try {
-      const accessToken = await invoke<string | null>('get_access_token')
-      if (!accessToken) return
-      
-      const response = await httpFetch<Stats>(`${API_URL}/api/user/stats`, {
-        method: 'GET',
-        headers: {
-          'Authorization': `Bearer ${accessToken}`
-        }
-      })
-      
-      if (response.ok) {
-        setStats(response.data)
-      }
+      setStats(await invoke<Stats>('get_user_stats'))
     } catch (error) {
       console.error('Failed to load stats:', error)
     }
   }
 
-  const handleBrowseFolder = async () => {
+  const handleBrowseFolder = async (defaultPath?: string) => {
     try {
-      const selected = await openDialog({
-        directory: true,
-        multiple: false,
-        title: 'Выберите папку для организации'
+      // The backend only grants access to folders picked in its own dialog
+      const selected = await invoke<string | null>('pick_folder', {
+        title: 'Выберите папку для организации',
+        defaultPath
       })
       if (typeof selected === 'string') {
         setSelectedFolder(selected)
@@ -188,14 +342,6 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
       setOrganizeStatus(`Найдено ${files.length} файлов. AI анализ...`)
       setOrganizeStep('analyzing')
       
-      // Get access token from Tauri
-      const accessToken = await invoke<string | null>('get_access_token')
-      
-      if (!accessToken) {
-        setOrganizeStatus('❌ Требуется авторизация. Перезайдите в приложение.')
-        setOrganizeStep('idle')
-        return
-      }
       // Step 2: Scan existing folders if option enabled
       let existingFolders: ExistingFolder[] = []
       if (useExistingFolders) {
@@ -227,33 +373,24 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
           await Promise.all(batch.map(async (file, batchIdx) => {
             const fileIdx = i + batchIdx
             try {
-              // Read first 50KB of file
-              const content = await invoke<number[]>('read_file_content', { 
+              // Read first 50KB of file, already base64-encoded by the backend
+              const content = await invoke<{ data: string }>('read_file_content', { 
                 filePath: file.path, 
                 maxBytes: 50000 
               })
               
-              if (content.length > 0) {
-                // Convert to base64
-                const bytes = new Uint8Array(content)
-                const base64 = btoa(String.fromCharCode(...bytes))
+              if (content.data.length > 0) {
+                const base64 = content.data
                 
                 // Send to backend for extraction
-                const extractResponse = await httpFetch<{ content_preview: string }>(`${API_URL}/api/auto-organize/extract-content`, {
-                  method: 'POST',
-                  headers: {
-                    'Content-Type': 'application/json',
-                    'Authorization': `Bearer ${accessToken || ''}`
-                  },
-                  body: Body.json({
-                    content_base64: base64,
-                    extension: file.extension,
-                    filename: file.filename
-                  })
+                const extracted = await invoke<{ content_preview: string }>('extract_content', {
+                  contentBase64: base64,
+                  extension: file.extension,
+                  filename: file.filename
                 })
                 
-                if (extractResponse.ok && extractResponse.data.content_preview) {
-                  filesForApi[fileIdx].content_preview = extractResponse.data.content_preview
+                if (extracted.content_preview) {
+                  filesForApi[fileIdx].content_preview = extracted.content_preview
                 }
               }
             } catch (e) {
@@ -267,13 +404,8 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
         setOrganizeStatus(`Анализ ${files.length} файлов...`)
       }
       
-      const response = await httpFetch<OrganizeResult>(`${API_URL}/api/auto-organize/analyze`, {
-        method: 'POST',
-        headers: { 
-          'Content-Type': 'application/json',
-          'Authorization': `Bearer ${accessToken || ''}`
-        },
-        body: Body.json({
+      const result = await invoke<OrganizeResult>('analyze_organize', {
+        request: {
           files: filesForApi,
           existing_folders: existingFolders.map(f => ({
             folder_name: f.folder_name,
@@ -287,17 +419,9 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
           custom_prompt: customPrompt,
           min_clusters: 3,
           max_clusters: 15
-        })
-      })
-      
-      if (!response.ok) {
-        if (response.status === 401) {
-          throw new Error('Сессия истекла. Перезайдите в Настройках → Выйти.')
         }
-        throw new Error(`API error: ${response.status}`)
-      }
+      })
       
-      const result = response.data
       setOrganizeResult(result)
       setOrganizeStep('preview')
       setOrganizeStatus(`Готово: ${result.total_folders} папок`)
@@ -335,35 +459,25 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
       
       if (result.success || result.moved_count > 0) {
         // Log actions to backend for history
-        const accessToken = await invoke<string | null>('get_access_token')
-        if (accessToken) {
-          for (const folder of organizeResult.folders) {
-            for (const filename of folder.files) {
-              const file = scannedFiles.find(f => f.filename === filename)
-              if (file) {
-                try {
-                  await httpFetch(`${API_URL}/api/actions/log`, {
-                    method: 'POST',
-                    headers: {
-                      'Content-Type': 'application/json',
-                      'Authorization': `Bearer ${accessToken}`
-                    },
-                    body: Body.json({
-                      filename: file.filename,
-                      source_path: file.path,
-                      dest_path: `${selectedFolder}\\${folder.folder_path}\\${filename}`,
-                      confidence: folder.confidence || 0.9
-                    })
-                  })
-                } catch (e) {
-                  console.warn('Failed to log action:', e)
-                }
+        for (const folder of organizeResult.folders) {
+          for (const filename of folder.files) {
+            const file = scannedFiles.find(f => f.filename === filename)
+            if (file) {
+              try {
+                await invoke('log_organize_action', {
+                  filename: file.filename,
+                  sourcePath: file.path,
+                  destPath: `${selectedFolder}\\${folder.folder_path}\\${filename}`,
+                  confidence: folder.confidence || 0.9
+                })
+              } catch (e) {
+                console.warn('Failed to log action:', e)
               }
             }
           }
-          // Refresh recent actions
-          loadRecentActions()
         }
+        // Refresh recent actions
+        loadRecentActions()
         
         setOrganizeStatus(`✅ Перемещено ${result.moved_count} файлов!`)
         setOrganizeStep('done')
@@ -382,15 +496,8 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
     if (!organizeResult) return
     
     try {
-      const accessToken = await invoke<string | null>('get_access_token')
-      
-      const response = await httpFetch<{ rules: SuggestedRule[], total_rules: number }>(`${API_URL}/api/auto-organize/generate-rules`, {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-          'Authorization': `Bearer ${accessToken || ''}`
-        },
-        body: Body.json({
+      const response = await invoke<{ rules: SuggestedRule[], total_rules: number }>('generate_rules', {
+        request: {
           folders: organizeResult.folders.map(f => ({
             folder_path: f.folder_path,
             folder_name: f.folder_name,
@@ -400,11 +507,11 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
             file_count: f.file_count
           })),
           source_folder: selectedFolder
-        })
+        }
       })
       
-      if (response.ok && response.data.rules.length > 0) {
-        setSuggestedRules(response.data.rules.map(r => ({ ...r, selected: true })))
+      if (response.rules.length > 0) {
+        setSuggestedRules(response.rules.map(r => ({ ...r, selected: true })))
         setShowRulesModal(true)
       } else {
         setOrganizeStatus('Не удалось найти паттерны для правил')
@@ -452,11 +559,11 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
         </div>
         <div className="stats-grid">
           <div className="stat-item">
-            <div className="stat-value">{stats?.files_sorted_today || 0}</div>
+            <div className="stat-value">{stats?.files_sorted_today ?? status.files_today}</div>
             <div className="stat-label">Сегодня</div>
           </div>
           <div className="stat-item">
-            <div className="stat-value">{stats?.files_sorted_this_week || 0}</div>
+            <div className="stat-value">{stats?.files_sorted_this_week ?? localStats?.total.files ?? 0}</div>
             <div className="stat-label">Неделя</div>
           </div>
           <div className="stat-item">
@@ -464,6 +571,44 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
             <div className="stat-label">Всего</div>
           </div>
         </div>
+        {dailyHistory.length > 0 && (
+          <div style={{ display: 'flex', alignItems: 'flex-end', gap: '2px', height: '48px', marginTop: '0.75rem' }}>
+            {dailyHistory.map((day) => {
+              const max = Math.max(1, ...dailyHistory.map((d) => d.sorted))
+              return (
+                <div
+                  key={day.day}
+                  title={`${day.day}: ${day.sorted} отсортировано, ${day.failed} ошибок`}
+                  style={{
+                    flex: 1,
+                    height: `${Math.max(4, (day.sorted / max) * 100)}%`,
+                    background: day.sorted > 0 ? 'var(--accent)' : 'var(--border)',
+                    borderRadius: '2px',
+                  }}
+                />
+              )
+            })}
+          </div>
+        )}
+        {localStats && localStats.by_category.length > 0 && (
+          <div style={{ marginTop: '0.75rem', fontSize: '0.75rem', color: 'var(--text-secondary)' }}>
+            {localStats.by_category.slice(0, 5).map((bucket) => (
+              <div key={bucket.key} style={{ display: 'flex', justifyContent: 'space-between' }}>
+                <span>{bucket.key || 'Без категории'}</span>
+                <span>
+                  {bucket.files} • {formatBytes(bucket.bytes)}
+                </span>
+              </div>
+            ))}
+            <div style={{ marginTop: '0.25rem' }}>
+              {Object.entries(localStats.methods)
+                .map(([method, count]) => `${method}: ${count}`)
+                .join(' • ')}
+              {localStats.total.average_confidence !== null &&
+                ` • уверенность ${Math.round(localStats.total.average_confidence * 100)}%`}
+            </div>
+          </div>
+        )}
       </div>
 
       {/* AI Auto-Organize */}
@@ -647,7 +792,7 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
                   {userFolders?.desktop && (
                     <button 
                       className={`btn ${selectedFolder === userFolders.desktop ? 'btn-primary' : 'btn-secondary'}`}
-                      onClick={() => setSelectedFolder(userFolders.desktop!)}
+                      onClick={() => handleBrowseFolder(userFolders.desktop!)}
                       style={{ flex: 1, fontSize: '0.7rem', padding: '0.4rem' }}
                     >
                       🖥️ Рабочий стол
@@ -656,7 +801,7 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
                   {userFolders?.downloads && (
                     <button 
                       className={`btn ${selectedFolder === userFolders.downloads ? 'btn-primary' : 'btn-secondary'}`}
-                      onClick={() => setSelectedFolder(userFolders.downloads!)}
+                      onClick={() => handleBrowseFolder(userFolders.downloads!)}
                       style={{ flex: 1, fontSize: '0.7rem', padding: '0.4rem' }}
                     >
                       📥 Загрузки
@@ -665,7 +810,7 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
                   {userFolders?.documents && (
                     <button 
                       className={`btn ${selectedFolder === userFolders.documents ? 'btn-primary' : 'btn-secondary'}`}
-                      onClick={() => setSelectedFolder(userFolders.documents!)}
+                      onClick={() => handleBrowseFolder(userFolders.documents!)}
                       style={{ flex: 1, fontSize: '0.7rem', padding: '0.4rem' }}
                     >
                       📄 Документы
@@ -686,7 +831,7 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
                       color: 'white', fontSize: '0.75rem'
                     }}
                   />
-                  <button className="btn btn-secondary" onClick={handleBrowseFolder} style={{ padding: '0.5rem' }}>
+                  <button className="btn btn-secondary" onClick={() => handleBrowseFolder()} style={{ padding: '0.5rem' }}>
                     📂
                   </button>
                 </div>
@@ -792,6 +937,23 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
         <div className="card-header">
           <span className="card-title">Последние действия</span>
         </div>
+        {problems.map((problem, i) => (
+          <div key={`${problem.path}-${i}`} style={{ color: 'var(--warning)', fontSize: '0.75rem', marginBottom: '0.25rem' }}>
+            ⚠️ {problem.path.split(/[\\/]/).pop()}: {problem.message}
+            {problem.file_id && (
+              <a href="#" onClick={(e) => { e.preventDefault(); toggleProblemLog(problem.file_id!) }} style={{ marginLeft: '0.5rem' }}>
+                почему?
+              </a>
+            )}
+            {problemLog && problemLog.file_id === problem.file_id && (
+              <div style={{ fontFamily: 'monospace', color: 'var(--text-secondary)', margin: '0.25rem 0 0 1rem' }}>
+                {problemLog.entries.map((entry, j) => (
+                  <div key={j}>{entry.level.toUpperCase()} {entry.message}</div>
+                ))}
+              </div>
+            )}
+          </div>
+        ))}
         {loading ? (
           <div className="loading">
             <div className="spinner"></div>
@@ -817,11 +979,74 @@ function Dashboard({ status, onTogglePause, onOpenDashboard }: DashboardProps) {
         )}
       </div>
 
+      {/* Files that arrived during a pause */}
+      {pausedQueue.length > 0 && (
+        <div className="card">
+          <div className="card-header">
+            <span className="card-title">📥 Пришли во время паузы ({pausedQueue.length})</span>
+          </div>
+          <div className="actions-list">
+            {pausedQueue.map((file) => (
+              <div key={file.path} className="action-item">
+                <span className="icon">{getFileIcon(file.path)}</span>
+                <div style={{ flex: 1, minWidth: 0 }}>
+                  <div className="filename">{file.path.split(/[\\/]/).pop()}</div>
+                  <div className="destination">{file.path}</div>
+                </div>
+                {!status.is_paused && (
+                  <button className="btn btn-secondary" onClick={() => handleSortQueued([file.path])} title="Сортировать">
+                    ✅
+                  </button>
+                )}
+                <button className="btn btn-secondary" onClick={() => handleDiscardQueued([file.path])} title="Не сортировать">
+                  ✖️
+                </button>
+              </div>
+            ))}
+          </div>
+          <div style={{ display: 'flex', gap: '0.5rem', marginTop: '0.5rem' }}>
+            <button className="btn btn-primary" onClick={() => handleSortQueued()} disabled={status.is_paused}>
+              Сортировать все
+            </button>
+            <button className="btn btn-secondary" onClick={() => handleDiscardQueued()}>
+              Очистить
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Actions */}
       <div className="card">
-        <button className="btn btn-secondary" onClick={onTogglePause} style={{ marginBottom: '0.5rem' }}>
-          {status.is_paused ? '▶️ Продолжить' : '⏸️ Пауза'}
-        </button>
+        {status.is_paused ? (
+          <>
+            <button className="btn btn-secondary" onClick={onTogglePause} style={{ marginBottom: '0.5rem' }}>
+              ▶️ Продолжить
+            </button>
+            {status.paused_until && (
+              <p style={{ fontSize: '0.75rem', color: 'var(--text-secondary)', marginBottom: '0.5rem' }}>
+                Пауза до {new Date(status.paused_until).toLocaleString('ru-RU', {
+                  day: 'numeric',
+                  month: 'short',
+                  hour: '2-digit',
+                  minute: '2-digit',
+                })}
+              </p>
+            )}
+          </>
+        ) : (
+          <div style={{ display: 'flex', gap: '0.25rem', marginBottom: '0.5rem' }}>
+            {PAUSE_OPTIONS.map(({ duration, label }) => (
+              <button
+                key={duration}
+                className="btn btn-secondary"
+                onClick={() => onPauseFor(duration)}
+                title="Приостановить сортировку"
+              >
+                ⏸️ {label}
+              </button>
+            ))}
+          </div>
+        )}
         <button className="btn btn-primary" onClick={onOpenDashboard}>
           🌐 Открыть Dashboard
         </button>
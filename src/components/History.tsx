@@ -0,0 +1,283 @@
+import { invoke } from '@tauri-apps/api/tauri'
+import { useEffect, useState } from 'react'
+
+export interface HistoryEntry {
+  origin: 'local' | 'server'
+  id: string
+  filename: string
+  source_path: string | null
+  dest_path: string
+  category: string | null
+  category_icon: string | null
+  rule_id: string | null
+  rule_name: string | null
+  confidence: number | null
+  method: string | null
+  size_bytes: number | null
+  timestamp: number
+  undone: boolean
+}
+
+export interface HistoryPage {
+  entries: HistoryEntry[]
+  page: number
+  per_page: number
+  total: number
+}
+
+export interface ServerHistoryPage {
+  entries: HistoryEntry[]
+  page: number
+  per_page: number
+  has_more: boolean
+}
+
+type HistorySource = 'local' | 'server'
+type HistorySort = 'newest' | 'oldest' | 'filename' | 'largest' | 'confidence'
+type ExportKind = 'history' | 'summary'
+type ExportFormat = 'csv' | 'jsonl'
+
+const PER_PAGE = 20
+
+function History() {
+  const [source, setSource] = useState<HistorySource>('local')
+  const [result, setResult] = useState<HistoryPage | null>(null)
+  const [serverResult, setServerResult] = useState<ServerHistoryPage | null>(null)
+  const [serverError, setServerError] = useState<string | null>(null)
+  const [page, setPage] = useState(1)
+  const [search, setSearch] = useState('')
+  const [category, setCategory] = useState('')
+  const [folder, setFolder] = useState('')
+  const [method, setMethod] = useState('')
+  const [from, setFrom] = useState('')
+  const [to, setTo] = useState('')
+  const [sort, setSort] = useState<HistorySort>('newest')
+  const [loading, setLoading] = useState(false)
+
+  useEffect(() => {
+    if (source === 'server') {
+      loadServerHistory()
+    } else {
+      loadHistory()
+    }
+  }, [source, page, from, to, sort])
+
+  // The server pages on its own and knows nothing of the local filters
+  const loadServerHistory = async () => {
+    setLoading(true)
+    try {
+      setServerResult(await invoke<ServerHistoryPage>('get_server_history', { query: { page, per_page: PER_PAGE } }))
+      setServerError(null)
+    } catch (error) {
+      setServerResult(null)
+      setServerError(String(error))
+    } finally {
+      setLoading(false)
+    }
+  }
+
+  const handleSource = (next: HistorySource) => {
+    setSource(next)
+    setPage(1)
+  }
+
+  const loadHistory = async () => {
+    setLoading(true)
+    try {
+      const query = {
+        page,
+        per_page: PER_PAGE,
+        search: search || null,
+        category: category || null,
+        folder: folder || null,
+        method: method || null,
+        from: from || null,
+        to: to || null,
+        sort,
+      }
+      setResult(await invoke<HistoryPage>('get_history', { query }))
+    } catch (error) {
+      console.error('Failed to load history:', error)
+    } finally {
+      setLoading(false)
+    }
+  }
+
+  // Text fields apply on Enter or when they lose focus
+  const applyText = () => {
+    if (page === 1) {
+      loadHistory()
+    } else {
+      setPage(1)
+    }
+  }
+
+  // Exports cover the selected date range, ignoring the other filters
+  const handleExport = async (kind: ExportKind, format: ExportFormat) => {
+    try {
+      const exported = await invoke<{ path: string; rows: number } | null>('export_history', {
+        kind,
+        format,
+        from: from || null,
+        to: to || null,
+      })
+      if (exported) {
+        alert(`Сохранено ${exported.rows} строк в ${exported.path}`)
+      }
+    } catch (error) {
+      alert(`Не удалось экспортировать: ${error}`)
+    }
+  }
+
+  const pages = result ? Math.max(1, Math.ceil(result.total / result.per_page)) : 1
+  const entries = (source === 'server' ? serverResult?.entries : result?.entries) ?? []
+  const hasNext = source === 'server' ? !!serverResult?.has_more : page < pages
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">🕘 История</span>
+        <select
+          className="form-input"
+          value={source}
+          onChange={(e) => handleSource(e.target.value as HistorySource)}
+          style={{ width: 'auto' }}
+        >
+          <option value="local">Этот компьютер</option>
+          <option value="server">Только на сервере</option>
+        </select>
+      </div>
+
+      {source === 'local' && (
+        <>
+          <div className="form-group">
+            <input
+              type="text"
+              className="form-input"
+              placeholder="Поиск по имени файла"
+              value={search}
+              onChange={(e) => setSearch(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && applyText()}
+              onBlur={applyText}
+            />
+          </div>
+
+          <div style={{ display: 'grid', gridTemplateColumns: '1fr 1fr', gap: '0.5rem', marginBottom: '0.75rem' }}>
+            <input
+              type="text"
+              className="form-input"
+              placeholder="Категория"
+              value={category}
+              onChange={(e) => setCategory(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && applyText()}
+              onBlur={applyText}
+            />
+            <input
+              type="text"
+              className="form-input"
+              placeholder="Способ классификации"
+              value={method}
+              onChange={(e) => setMethod(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && applyText()}
+              onBlur={applyText}
+            />
+            <input
+              type="text"
+              className="form-input"
+              placeholder="Папка"
+              value={folder}
+              onChange={(e) => setFolder(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && applyText()}
+              onBlur={applyText}
+            />
+            <select className="form-input" value={sort} onChange={(e) => setSort(e.target.value as HistorySort)}>
+              <option value="newest">Сначала новые</option>
+              <option value="oldest">Сначала старые</option>
+              <option value="filename">По имени</option>
+              <option value="largest">По размеру</option>
+              <option value="confidence">По уверенности</option>
+            </select>
+            <input type="date" className="form-input" value={from} onChange={(e) => { setFrom(e.target.value); setPage(1) }} />
+            <input type="date" className="form-input" value={to} onChange={(e) => { setTo(e.target.value); setPage(1) }} />
+          </div>
+        </>
+      )}
+
+      {source === 'server' && serverError && (
+        <div style={{ fontSize: '0.75rem', color: 'var(--warning)', marginBottom: '0.5rem' }}>
+          Сервер недоступен: {serverError}
+        </div>
+      )}
+
+      {loading && entries.length === 0 ? (
+        <div className="loading">
+          <div className="spinner"></div>
+        </div>
+      ) : entries.length > 0 ? (
+        <div className="actions-list">
+          {entries.map((entry) => (
+            <div
+              key={`${entry.origin}-${entry.id}`}
+              className="action-item"
+              style={{ opacity: entry.undone ? 0.5 : 1 }}
+              title={entry.source_path ? `${entry.source_path} → ${entry.dest_path}` : entry.dest_path}
+            >
+              <span className="icon">{entry.category_icon || (entry.origin === 'server' ? '☁️' : '📄')}</span>
+              <div style={{ flex: 1, minWidth: 0 }}>
+                <div className="filename">{entry.filename}</div>
+                <div className="destination">
+                  → {entry.dest_path.split(/[\\/]/).slice(-2, -1)[0]}
+                  {entry.rule_name && ` • ${entry.rule_name}`}
+                  {entry.confidence !== null && ` • ${Math.round(entry.confidence * 100)}%`}
+                  {entry.undone && ' • отменено'}
+                </div>
+              </div>
+              <span className="time">
+                {new Date(entry.timestamp * 1000).toLocaleString('ru-RU', {
+                  day: 'numeric',
+                  month: 'short',
+                  hour: '2-digit',
+                  minute: '2-digit',
+                })}
+              </span>
+            </div>
+          ))}
+        </div>
+      ) : (
+        <div className="empty-state">
+          <div className="icon">📭</div>
+          <p>Ничего не найдено</p>
+        </div>
+      )}
+
+      <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', marginTop: '0.75rem' }}>
+        <button className="btn btn-secondary" disabled={page <= 1} onClick={() => setPage(page - 1)} style={{ width: 'auto' }}>
+          ←
+        </button>
+        <span style={{ fontSize: '0.8rem', color: 'var(--text-secondary)' }}>
+          {source === 'server' ? `стр. ${page}` : `${page} / ${pages} • ${result?.total ?? 0} записей`}
+        </span>
+        <button className="btn btn-secondary" disabled={!hasNext} onClick={() => setPage(page + 1)} style={{ width: 'auto' }}>
+          →
+        </button>
+      </div>
+
+      <div style={{ display: 'flex', gap: '0.5rem', marginTop: '0.75rem' }}>
+        <button className="btn btn-secondary" onClick={() => handleExport('history', 'csv')}>
+          ⬇️ История CSV
+        </button>
+        <button className="btn btn-secondary" onClick={() => handleExport('history', 'jsonl')}>
+          ⬇️ JSONL
+        </button>
+        <button className="btn btn-secondary" onClick={() => handleExport('summary', 'csv')}>
+          📋 Отчёт CSV
+        </button>
+        <button className="btn btn-secondary" onClick={() => handleExport('summary', 'jsonl')}>
+          📋 JSONL
+        </button>
+      </div>
+    </div>
+  )
+}
+
+export default History
@@ -0,0 +1,118 @@
+import { invoke } from '@tauri-apps/api/tauri'
+import { useEffect, useState } from 'react'
+
+export interface LogEntry {
+  ts: string
+  level: 'error' | 'warn' | 'info' | 'debug' | 'trace'
+  target: string
+  message: string
+  file_id?: string
+}
+
+type LogLevel = 'error' | 'warn' | 'info' | 'debug'
+
+const LEVEL_COLORS: Record<string, string> = {
+  error: 'var(--error)',
+  warn: 'var(--warning)',
+}
+
+function Logs() {
+  const [entries, setEntries] = useState<LogEntry[]>([])
+  const [level, setLevel] = useState<LogLevel>('info')
+  const [from, setFrom] = useState('')
+  const [to, setTo] = useState('')
+  const [search, setSearch] = useState('')
+  const [fileId, setFileId] = useState('')
+  const [loading, setLoading] = useState(false)
+
+  useEffect(() => {
+    loadLogs()
+  }, [level, from, to, fileId])
+
+  const loadLogs = async () => {
+    setLoading(true)
+    try {
+      const query = {
+        level,
+        from: from ? new Date(from).toISOString() : null,
+        to: to ? new Date(to).toISOString() : null,
+        file_id: fileId || null,
+        search: search || null,
+        limit: 300,
+      }
+      const result = await invoke<LogEntry[]>('get_logs', { query })
+      setEntries(result.reverse())
+    } catch (error) {
+      console.error('Failed to load logs:', error)
+    } finally {
+      setLoading(false)
+    }
+  }
+
+  return (
+    <div className="card">
+      <div className="card-header">
+        <span className="card-title">📜 Журнал</span>
+        <button className="btn btn-secondary" onClick={loadLogs} style={{ width: 'auto' }}>
+          🔄
+        </button>
+      </div>
+
+      <div style={{ display: 'grid', gridTemplateColumns: '1fr 1fr', gap: '0.5rem', marginBottom: '0.75rem' }}>
+        <select className="form-input" value={level} onChange={(e) => setLevel(e.target.value as LogLevel)}>
+          <option value="error">Только ошибки</option>
+          <option value="warn">Предупреждения</option>
+          <option value="info">Информация</option>
+          <option value="debug">Отладка</option>
+        </select>
+        <input
+          type="text"
+          className="form-input"
+          placeholder="Поиск по тексту"
+          value={search}
+          onChange={(e) => setSearch(e.target.value)}
+          onKeyDown={(e) => e.key === 'Enter' && loadLogs()}
+          onBlur={loadLogs}
+        />
+        <input type="datetime-local" className="form-input" value={from} onChange={(e) => setFrom(e.target.value)} />
+        <input type="datetime-local" className="form-input" value={to} onChange={(e) => setTo(e.target.value)} />
+      </div>
+
+      {fileId && (
+        <div style={{ fontSize: '0.75rem', marginBottom: '0.5rem' }}>
+          Файл {fileId}{' '}
+          <button className="btn btn-secondary" onClick={() => setFileId('')} style={{ width: 'auto' }}>
+            ✕
+          </button>
+        </div>
+      )}
+
+      {loading && entries.length === 0 ? (
+        <div className="loading">
+          <div className="spinner"></div>
+        </div>
+      ) : entries.length > 0 ? (
+        <div style={{ fontFamily: 'monospace', fontSize: '0.7rem', maxHeight: '24rem', overflowY: 'auto' }}>
+          {entries.map((entry, i) => (
+            <div key={`${entry.ts}-${i}`} style={{ color: LEVEL_COLORS[entry.level], marginBottom: '0.25rem' }}>
+              {new Date(entry.ts).toLocaleString('ru-RU')} {entry.level.toUpperCase()}{' '}
+              {entry.file_id && (
+                <a href="#" onClick={(e) => { e.preventDefault(); setFileId(entry.file_id!) }}>
+                  [{entry.file_id}]
+                </a>
+              )}{' '}
+              {entry.message}
+            </div>
+          ))}
+        </div>
+      ) : (
+        <div className="empty-state">
+          <div className="icon">📭</div>
+          <p>Записей нет</p>
+        </div>
+      )}
+    </div>
+  )
+}
+
+export default Logs
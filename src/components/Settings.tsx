@@ -1,14 +1,49 @@
-import { open } from '@tauri-apps/api/dialog'
 import { invoke } from '@tauri-apps/api/tauri'
 import { useEffect, useState } from 'react'
 
+interface WatchedFolder {
+  path: string
+  enabled: boolean
+}
+
 interface Config {
   api_url: string
   dashboard_url: string
-  watched_folders: string[]
+  watched_folders: WatchedFolder[]
   show_notifications: boolean
   start_on_boot: boolean
   processing_delay_seconds: number
+  review_paused_files: boolean
+}
+
+type ConfigSource = 'default' | 'file' | 'env' | 'cli'
+
+interface ConfigView {
+  config: Config
+  sources: Record<string, ConfigSource>
+}
+
+interface ProfileInfo {
+  name: string
+  is_active: boolean
+  api_url: string
+  watched_folders: string[]
+  is_logged_in: boolean
+}
+
+interface FieldIssue {
+  field: string
+  message: string
+}
+
+interface ValidationReport {
+  errors: FieldIssue[]
+  warnings: FieldIssue[]
+}
+
+function isValidationReport(value: unknown): value is ValidationReport {
+  const report = value as ValidationReport | null
+  return typeof report === 'object' && report !== null && Array.isArray(report.errors) && Array.isArray(report.warnings)
 }
 
 interface SettingsProps {
@@ -18,17 +53,54 @@ interface SettingsProps {
 
 function Settings({ onLogout, onRefresh }: SettingsProps) {
   const [config, setConfig] = useState<Config | null>(null)
+  const [sources, setSources] = useState<Record<string, ConfigSource>>({})
   const [loading, setLoading] = useState(true)
   const [saving, setSaving] = useState(false)
+  const [issues, setIssues] = useState<ValidationReport>({ errors: [], warnings: [] })
+  const [profiles, setProfiles] = useState<ProfileInfo[]>([])
+  const [newProfileName, setNewProfileName] = useState('')
 
   useEffect(() => {
     loadConfig()
+    loadProfiles()
   }, [])
 
+  const loadProfiles = async () => {
+    try {
+      setProfiles(await invoke<ProfileInfo[]>('list_profiles'))
+    } catch (error) {
+      console.error('Failed to load profiles:', error)
+    }
+  }
+
+  const handleSwitchProfile = async (name: string) => {
+    try {
+      await invoke('switch_profile', { name })
+      await Promise.all([loadConfig(), loadProfiles()])
+      await onRefresh()
+    } catch (error) {
+      console.error('Failed to switch profile:', error)
+    }
+  }
+
+  const handleCreateProfile = async (cloneFrom: string | null) => {
+    if (!newProfileName.trim()) return
+    try {
+      await invoke('create_profile', { name: newProfileName, cloneFrom })
+      setNewProfileName('')
+      await loadProfiles()
+    } catch (error) {
+      console.error('Failed to create profile:', error)
+    }
+  }
+
+  const activeProfile = profiles.find((p) => p.is_active)
+
   const loadConfig = async () => {
     try {
-      const result = await invoke<Config>('get_config')
-      setConfig(result)
+      const result = await invoke<ConfigView>('get_config')
+      setConfig(result.config)
+      setSources(result.sources)
     } catch (error) {
       console.error('Failed to load config:', error)
     } finally {
@@ -39,10 +111,18 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
   const saveConfig = async (newConfig: Config) => {
     setSaving(true)
     try {
-      await invoke('save_config', { config: newConfig })
-      setConfig(newConfig)
+      const report = await invoke<ValidationReport>('save_config', { config: newConfig })
+      setIssues(report)
+      // Reload rather than keep newConfig, since overridden fields are not saved
+      await loadConfig()
       await onRefresh()
     } catch (error) {
+      // Rejected configs come back as a validation report, anything else is a plain error
+      setIssues(
+        isValidationReport(error)
+          ? error
+          : { errors: [{ field: '', message: String(error) }], warnings: [] }
+      )
       console.error('Failed to save config:', error)
     } finally {
       setSaving(false)
@@ -51,14 +131,12 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
 
   const handleAddFolder = async () => {
     try {
-      const selected = await open({
-        directory: true,
-        multiple: false,
+      const selected = await invoke<string | null>('pick_folder', {
         title: 'Выберите папку для мониторинга',
       })
 
       if (selected && config) {
-        const newFolders = [...config.watched_folders, selected as string]
+        const newFolders = [...config.watched_folders, { path: selected, enabled: true }]
         await saveConfig({ ...config, watched_folders: newFolders })
       }
     } catch (error) {
@@ -68,7 +146,7 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
 
   const handleRemoveFolder = async (folder: string) => {
     if (config) {
-      const newFolders = config.watched_folders.filter((f) => f !== folder)
+      const newFolders = config.watched_folders.filter((f) => f.path !== folder)
       await saveConfig({ ...config, watched_folders: newFolders })
     }
   }
@@ -85,6 +163,23 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
     }
   }
 
+  const handleToggleReviewPaused = async () => {
+    if (config) {
+      await saveConfig({ ...config, review_paused_files: !config.review_paused_files })
+    }
+  }
+
+  // Hint for values that come from the environment or command line
+  const overrideNote = (field: string) => {
+    const source = sources[field]
+    if (source !== 'env' && source !== 'cli') return null
+    return (
+      <div style={{ color: 'var(--text-secondary)', fontSize: '0.75rem' }}>
+        {source === 'env' ? 'Задано переменной окружения' : 'Задано параметром запуска'} — изменения не сохранятся
+      </div>
+    )
+  }
+
   if (loading || !config) {
     return (
       <div className="loading">
@@ -95,14 +190,56 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
 
   return (
     <>
+      {/* Profiles */}
+      <div className="card">
+        <div className="card-header">
+          <span className="card-title">👤 Профиль</span>
+        </div>
+        <div className="form-group">
+          <select
+            className="form-input"
+            value={activeProfile?.name ?? ''}
+            onChange={(e) => handleSwitchProfile(e.target.value)}
+          >
+            {profiles.map((profile) => (
+              <option key={profile.name} value={profile.name}>
+                {profile.name}
+                {profile.is_logged_in ? '' : ' (не авторизован)'}
+              </option>
+            ))}
+          </select>
+        </div>
+        <div className="form-group">
+          <input
+            type="text"
+            className="form-input"
+            placeholder="Название нового профиля"
+            value={newProfileName}
+            onChange={(e) => setNewProfileName(e.target.value)}
+          />
+        </div>
+        <div style={{ display: 'flex', gap: '0.5rem' }}>
+          <button className="btn btn-secondary" onClick={() => handleCreateProfile(null)}>
+            ➕ Создать
+          </button>
+          <button
+            className="btn btn-secondary"
+            onClick={() => handleCreateProfile(activeProfile?.name ?? null)}
+          >
+            📋 Копировать текущий
+          </button>
+        </div>
+      </div>
+
       {/* Watched Folders */}
       <div className="card">
         <div className="card-header">
           <span className="card-title">📁 Отслеживаемые папки</span>
         </div>
+        {overrideNote('watched_folders')}
         <div className="folder-list">
           {config.watched_folders.length > 0 ? (
-            config.watched_folders.map((folder) => (
+            config.watched_folders.map(({ path: folder }) => (
               <div key={folder} className="folder-item">
                 <span className="path" title={folder}>
                   {folder.split('\\').pop() || folder}
@@ -160,6 +297,18 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
             <span className="toggle-slider"></span>
           </label>
         </div>
+
+        <div className="toggle-container">
+          <span className="toggle-label">Проверять файлы после паузы</span>
+          <label className="toggle">
+            <input
+              type="checkbox"
+              checked={config.review_paused_files}
+              onChange={handleToggleReviewPaused}
+            />
+            <span className="toggle-slider"></span>
+          </label>
+        </div>
       </div>
 
       {/* Server */}
@@ -176,6 +325,7 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
             onChange={(e) => setConfig({ ...config, api_url: e.target.value })}
             onBlur={() => saveConfig(config)}
           />
+          {overrideNote('api_url')}
         </div>
       </div>
 
@@ -186,6 +336,21 @@ function Settings({ onLogout, onRefresh }: SettingsProps) {
         </button>
       </div>
 
+      {[...issues.errors, ...issues.warnings].length > 0 && (
+        <div className="card">
+          {issues.errors.map((issue) => (
+            <div key={`e-${issue.field}-${issue.message}`} style={{ color: 'var(--error)', fontSize: '0.8rem' }}>
+              ❌ {issue.message}
+            </div>
+          ))}
+          {issues.warnings.map((issue) => (
+            <div key={`w-${issue.field}-${issue.message}`} style={{ color: 'var(--warning)', fontSize: '0.8rem' }}>
+              ⚠️ {issue.message}
+            </div>
+          ))}
+        </div>
+      )}
+
       {saving && (
         <div style={{ textAlign: 'center', color: 'var(--text-secondary)', marginTop: '0.5rem' }}>
           Сохранение...
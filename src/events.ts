@@ -0,0 +1,54 @@
+// Payloads of the events the backend emits; keep in sync with src-tauri/src/events.rs
+
+export interface FileDetected {
+  path: string
+  queued: boolean
+}
+
+export interface FileSorted {
+  source: string
+  destination: string
+  category: string
+  rule_id: string | null
+  rule_name: string | null
+  confidence: number
+  method: string
+}
+
+export interface FileSkipped {
+  path: string
+  reason: string
+  duplicate_of: string | null
+  file_id: string | null
+}
+
+export interface FileFailed {
+  path: string
+  error: string
+  file_id: string | null
+}
+
+export interface QueueChanged {
+  count: number
+}
+
+export interface ConnectivityChanged {
+  online: boolean
+}
+
+export interface WatcherStateChanged {
+  running: boolean
+  paused: boolean
+  paused_until: string | null
+  folders: string[]
+}
+
+export interface EventPayloads {
+  'file-detected': FileDetected
+  'file-sorted': FileSorted
+  'file-skipped': FileSkipped
+  'file-failed': FileFailed
+  'queue-changed': QueueChanged
+  'connectivity-changed': ConnectivityChanged
+  'watcher-state-changed': WatcherStateChanged
+}